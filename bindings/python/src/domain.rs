@@ -28,6 +28,11 @@ impl AccountDomain {
         self.0.alias.clone()
     }
 
+    #[getter]
+    pub fn defined_in(&self) -> Option<String> {
+        self.0.defined_in.as_ref().map(|it| it.to_string_lossy().to_string())
+    }
+
     pub fn __repr__(&self) -> String {
         format!("<AccountDomain: {}>", &self.0.name)
     }
@@ -5,6 +5,12 @@ export enum LoadingState {
   Refreshing = 'Refreshing',
 }
 
+/**
+ * decimal number is serialized as string by server to keep its full precision,
+ * parse it with `BigNumber` instead of `parseFloat` before calculating.
+ */
+export type Decimal = string;
+
 export interface Pageable<T> {
   total_count: number;
   total_page: number;
@@ -30,6 +36,7 @@ export interface Account {
   name: string;
   status: AccountStatus;
   alias?: String;
+  defined_in?: string;
   amount: CalculatedAmountResponse;
 }
 
@@ -37,6 +44,7 @@ export interface AccountInfo {
   name: string;
   status: AccountStatus;
   alias?: String;
+  defined_in?: string;
   amount: CalculatedAmountResponse;
 }
 
@@ -60,21 +68,22 @@ export interface AccountJournalItem {
   account: string;
   payee: string;
   narration?: string;
-  inferred_unit_number: string;
+  inferred_unit_number: Decimal;
   inferred_unit_commodity: string;
-  account_after_number: number;
+  account_after_number: Decimal;
   account_after_commodity: string;
 }
 
 export interface CommodityListItem {
   name: string;
   precision: number;
+  balance_precision: number;
   prefix: string;
   suffix: string;
   rounding: string;
-  total_amount: string;
+  total_amount: Decimal;
   latest_price_date: string;
-  latest_price_amount: string;
+  latest_price_amount: Decimal;
   latest_price_commodity: string;
 }
 
@@ -87,15 +96,15 @@ export interface CommodityDetail {
 
 export interface CommodityLot {
   datetime?: string;
-  amount: string;
-  price_amount?: string;
+  amount: Decimal;
+  price_amount?: Decimal;
   price_commodity?: string;
   account: string;
 }
 
 export interface CommodityPrice {
   datetime: string;
-  amount: string;
+  amount: Decimal;
   target_commodity: string;
 }
 
@@ -142,23 +151,25 @@ export interface JournalTransactionItem {
   links: any[];
   flag: string;
   is_balanced: boolean;
+  is_transfer: boolean;
+  rounding_residual: AmountResponse[] | null;
   postings: Posting[];
   metas: Meta[];
 }
 
 export interface Posting {
   account: string;
-  unit_number?: string;
+  unit_number?: Decimal;
   unit_commodity?: string;
-  cost_number: string;
+  cost_number: Decimal;
   cost_commodity: string;
-  price_number: string;
+  price_number: Decimal;
   price_commodity: string;
-  inferred_unit_number: string;
+  inferred_unit_number: Decimal;
   inferred_unit_commodity: string;
-  account_before_number: string;
+  account_before_number: Decimal;
   account_before_commodity: string;
-  account_after_number: string;
+  account_after_number: Decimal;
   account_after_commodity: string;
 }
 
@@ -178,11 +189,11 @@ export interface StatisticGraphResponse {
 
 export interface CalculatedAmountResponse {
   calculated: AmountResponse;
-  detail: { [commodity: string]: string };
+  detail: { [commodity: string]: Decimal };
 }
 
 export interface AmountResponse {
-  number: string;
+  number: Decimal;
   currency: string;
 }
 
@@ -249,6 +260,6 @@ export interface BudgetIntervalEventPostingResponse {
   trx_id: string;
   payee: string;
   narration?: string;
-  inferred_unit_number: string;
+  inferred_unit_number: Decimal;
   inferred_unit_commodity: string;
 }
@@ -8,7 +8,11 @@ export enum LedgerErrorType {
   AccountClosed = 'AccountClosed',
   TransactionDoesNotBalance = 'TransactionDoesNotBalance',
   CommodityDoesNotDefine = 'CommodityDoesNotDefine',
+  DefaultCommodityDoesNotDefine = 'DefaultCommodityDoesNotDefine',
+  CurrencySymbolDoesNotDefine = 'CurrencySymbolDoesNotDefine',
   TransactionHasMultipleImplicitPosting = 'TransactionHasMultipleImplicitPosting',
+  DocumentPathOutsideBase = 'DocumentPathOutsideBase',
+  InvalidOptionValue = 'InvalidOptionValue',
 }
 
 export interface LedgerError {
@@ -25,7 +25,7 @@ impl AccountType {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash, serde::Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Account {
     pub account_type: AccountType,
     pub content: String,
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
 
 use bigdecimal::{BigDecimal, Zero};
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Clone, Debug, Serialize)]
 pub struct CalculatedAmount {
@@ -21,12 +22,20 @@ impl CalculatedAmount {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone, Serialize)]
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct Amount {
+    #[serde(deserialize_with = "deserialize_number")]
     pub number: BigDecimal,
     pub currency: String,
 }
 
+/// `BigDecimal` is serialized as string, so it is deserialized from string explicitly
+/// to support those non-self-describing formats, e.g. bincode.
+fn deserialize_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigDecimal, D::Error> {
+    let number = String::deserialize(deserializer)?;
+    BigDecimal::from_str(&number).map_err(serde::de::Error::custom)
+}
+
 impl Amount {
     pub fn new(number: BigDecimal, currency: impl Into<String>) -> Amount {
         Amount {
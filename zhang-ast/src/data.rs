@@ -1,10 +1,12 @@
 use std::collections::HashSet;
 use std::ops::{Div, Mul, Neg};
 
+use bigdecimal::Zero;
 use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use chrono_tz::Tz;
 use indexmap::IndexSet;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 use crate::amount::Amount;
 use crate::error::ErrorKind;
@@ -15,7 +17,7 @@ use crate::Account;
 
 pub type Meta = MultiValueMap<String, ZhangString>;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Date {
     Date(NaiveDate),
     DateHour(NaiveDateTime),
@@ -51,7 +53,7 @@ impl Date {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Open {
     pub date: Date,
     pub account: Account,
@@ -59,28 +61,28 @@ pub struct Open {
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Close {
     pub date: Date,
     pub account: Account,
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Commodity {
     pub date: Date,
     pub currency: String,
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct BalanceCheck {
     pub date: Date,
     pub account: Account,
     pub amount: Amount,
     pub meta: Meta,
 }
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct BalancePad {
     pub date: Date,
     pub account: Account,
@@ -90,7 +92,7 @@ pub struct BalancePad {
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Posting {
     pub flag: Option<Flag>,
     pub account: Account,
@@ -108,7 +110,7 @@ impl Posting {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     pub date: Date,
     pub flag: Option<Flag>,
@@ -134,7 +136,7 @@ impl Transaction {
         Ok(inventory)
     }
 
-    pub fn txn_postings(&self) -> Vec<TxnPosting> {
+    pub fn txn_postings(&self) -> Vec<TxnPosting<'_>> {
         self.postings.iter().map(|posting| TxnPosting { txn: self, posting }).collect_vec()
     }
     pub fn has_account(&self, name: &String) -> bool {
@@ -228,12 +230,18 @@ impl<'a> TxnPosting<'a> {
         }
     }
 
+    /// posting with zero units and a cost like `Assets:Broker 0 AAPL {+5 USD}` adjusts the cost basis of each held lot by the given cost per unit,
+    /// which is used for return of capital or wash sale adjustment.
+    pub fn is_cost_adjustment(&self) -> bool {
+        self.posting.cost.is_some() && self.posting.units.as_ref().map(|unit| unit.number.is_zero()).unwrap_or(false)
+    }
+
     pub fn account_name(&self) -> String {
         self.posting.account.content.clone()
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Note {
     pub date: Date,
     pub account: Account,
@@ -244,7 +252,7 @@ pub struct Note {
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Event {
     pub date: Date,
 
@@ -254,7 +262,7 @@ pub struct Event {
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Query {
     pub date: Date,
 
@@ -264,7 +272,7 @@ pub struct Query {
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Price {
     pub date: Date,
 
@@ -274,7 +282,7 @@ pub struct Price {
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Document {
     pub date: Date,
 
@@ -285,7 +293,7 @@ pub struct Document {
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Custom {
     pub date: Date,
 
@@ -294,29 +302,29 @@ pub struct Custom {
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Options {
     pub key: ZhangString,
     pub value: ZhangString,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Plugin {
     pub module: ZhangString,
     pub value: Vec<ZhangString>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Include {
     pub file: ZhangString,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Comment {
     pub content: String,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Budget {
     pub date: Date,
     pub name: String,
@@ -325,7 +333,7 @@ pub struct Budget {
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct BudgetAdd {
     pub date: Date,
     pub name: String,
@@ -333,7 +341,7 @@ pub struct BudgetAdd {
 
     pub meta: Meta,
 }
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct BudgetTransfer {
     pub date: Date,
     pub from: String,
@@ -343,7 +351,7 @@ pub struct BudgetTransfer {
     pub meta: Meta,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct BudgetClose {
     pub date: Date,
     pub name: String,
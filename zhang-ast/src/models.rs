@@ -31,7 +31,7 @@ pub enum DirectiveType {
     BudgetClose,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Directive {
     Open(Open),
     Close(Close),
@@ -130,13 +130,13 @@ impl Directive {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum StringOrAccount {
     String(ZhangString),
     Account(Account),
 }
 
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub enum ZhangString {
     UnquoteString(String),
     QuoteString(String),
@@ -163,7 +163,7 @@ impl ZhangString {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum SingleTotalPrice {
     Single(Amount),
     Total(Amount),
@@ -191,6 +191,15 @@ pub enum Rounding {
     RoundDown,
 }
 
+/// the order to reduce lots of commodity with cost when selling
+#[derive(EnumString, Debug, PartialEq, Eq, Deserialize, Serialize, Clone, Copy, Display)]
+pub enum BookingMethod {
+    #[strum(serialize = "FIFO")]
+    Fifo,
+    #[strum(serialize = "LIFO")]
+    Lifo,
+}
+
 impl Rounding {
     pub fn is_up(&self) -> bool {
         match self {
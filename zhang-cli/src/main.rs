@@ -1,12 +1,21 @@
 use std::fmt::Debug;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 
-use clap::{Args, Parser};
+use beancount::Beancount;
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand};
 use env_logger::Env;
 use log::{error, info, LevelFilter};
 use self_update::Status;
 use tokio::task::spawn_blocking;
+use zhang_ast::{Account, Date};
+use zhang_core::data_source::LocalFileSystemDataSource;
+use zhang_core::data_type::text::ZhangDataType;
+use zhang_core::data_type::DataType;
+use zhang_core::ledger::Ledger;
+use zhang_core::utils::local_datetime;
 use zhang_server::ServeConfig;
 
 use crate::opendal::OpendalDataSource;
@@ -25,6 +34,18 @@ pub enum Opts {
     /// start an internal server with frontend ui
     Serve(ServerOpts),
 
+    /// generate reports of ledger
+    Report(ReportOpts),
+
+    /// print the processed directives as zhang text, including resolved implicit postings and generated pad transactions as comments
+    Print(PrintOpts),
+
+    /// inline all included files into one zhang file in load order
+    Flatten(FlattenOpts),
+
+    /// transfer balances of income and expenses accounts into equity account, and append the closing transaction into ledger
+    CloseBooks(CloseBooksOpts),
+
     /// self update
     Update {
         #[clap(short, long)]
@@ -59,6 +80,79 @@ pub struct ExportOpts {
     pub exporter: Exporter,
 }
 
+#[derive(Args, Debug)]
+pub struct PrintOpts {
+    /// base path of zhang project
+    pub path: PathBuf,
+
+    /// the endpoint of main zhang file.
+    #[clap(short, long, default_value = "main.zhang")]
+    pub endpoint: String,
+
+    /// binary cache file of parsed ledger, parsing is skipped if source files are not changed
+    #[clap(long)]
+    pub cache: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct FlattenOpts {
+    /// base path of zhang project
+    pub path: PathBuf,
+
+    /// the endpoint of main zhang file.
+    #[clap(short, long, default_value = "main.zhang")]
+    pub endpoint: String,
+
+    /// the target file of flattened ledger, print to stdout if not present
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct CloseBooksOpts {
+    /// base path of zhang project
+    pub path: PathBuf,
+
+    /// the endpoint of main zhang file.
+    #[clap(short, long, default_value = "main.zhang")]
+    pub endpoint: String,
+
+    /// the end date of closing period, balances until this date are closed
+    #[clap(long)]
+    pub date: NaiveDate,
+
+    /// the equity account which income and expenses are transferred into
+    #[clap(long)]
+    pub equity: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ReportOpts {
+    #[clap(subcommand)]
+    pub report: Report,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Report {
+    /// list debit and credit of each account, exit with non-zero code if ledger is unbalanced
+    TrialBalance {
+        /// base path of zhang project
+        path: PathBuf,
+
+        /// the endpoint of main zhang file.
+        #[clap(short, long, default_value = "main.zhang")]
+        endpoint: String,
+
+        /// the date of trial balance, balances are calculated until the end of this date
+        #[clap(long)]
+        date: NaiveDate,
+
+        /// binary cache file of parsed ledger, parsing is skipped if source files are not changed
+        #[clap(long)]
+        cache: Option<PathBuf>,
+    },
+}
+
 #[derive(Debug, Clone, clap::ValueEnum)]
 pub enum Exporter {
     Text,
@@ -112,6 +206,99 @@ pub struct ServerOpts {
     pub no_report: bool,
 }
 
+/// load ledger from local file system, the data type is detected by the extension of endpoint
+fn load_local_ledger(path: PathBuf, endpoint: String, cache: Option<PathBuf>) -> Ledger {
+    let data_source = match endpoint.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("bc") | Some("bean") => LocalFileSystemDataSource::new(Beancount {}),
+        _ => LocalFileSystemDataSource::new(ZhangDataType {}),
+    };
+    match cache {
+        Some(cache_file) => Ledger::load_with_cache(path, endpoint, Arc::new(data_source), cache_file),
+        None => Ledger::load_with_data_source(path, endpoint, Arc::new(data_source)),
+    }
+    .expect("Cannot load ledger")
+}
+
+impl PrintOpts {
+    pub fn run(self) {
+        let ledger = load_local_ledger(self.path, self.endpoint, self.cache);
+        let data_type = ZhangDataType {};
+        let directives = ledger.processed_directives().expect("cannot process directives");
+        for directive in directives {
+            println!("{}\n", data_type.export(directive));
+        }
+    }
+}
+
+impl FlattenOpts {
+    pub fn run(self) {
+        let ledger = load_local_ledger(self.path, self.endpoint, None);
+        let flattened = ZhangDataType {}.flatten(&ledger).expect("cannot flatten ledger");
+        match self.output {
+            Some(output) => {
+                std::fs::write(&output, flattened).expect("cannot write flattened ledger");
+                info!("flattened {} files into {}", ledger.visited_files.len(), output.display());
+            }
+            None => print!("{}", flattened),
+        }
+    }
+}
+
+impl CloseBooksOpts {
+    pub fn run(self) {
+        let ledger = load_local_ledger(self.path, self.endpoint, None);
+        let equity_account = Account::from_str(&self.equity).expect("invalid equity account");
+        let closing = ledger.generate_closing(Date::Date(self.date), &equity_account);
+        if closing.is_empty() {
+            info!("no income or expenses to close until {}", self.date);
+            return;
+        }
+        ledger.append(closing).expect("cannot append closing transaction");
+        info!("books are closed until {} into {}", self.date, self.equity);
+    }
+}
+
+impl Report {
+    pub fn run(self) {
+        match self {
+            Report::TrialBalance { path, endpoint, date, cache } => {
+                let ledger = load_local_ledger(path, endpoint, cache);
+                let mut operations = ledger.operations();
+                let trial_balance = match local_datetime(&ledger.options.timezone, date.and_hms_opt(23, 59, 59).expect("invalid time"))
+                    .and_then(|datetime| operations.trial_balance(datetime))
+                {
+                    Ok(trial_balance) => trial_balance,
+                    Err(e) => {
+                        error!("cannot calculate trial balance: {}", e);
+                        std::process::exit(1);
+                    }
+                };
+
+                let width = trial_balance.items.iter().map(|it| it.account.len()).max().unwrap_or(0).max(5);
+                println!("Trial Balance as of {} ({})", date, trial_balance.commodity);
+                println!("{:<width$}  {:>16}  {:>16}", "Account", "Debit", "Credit", width = width);
+                for item in &trial_balance.items {
+                    println!("{:<width$}  {:>16}  {:>16}", item.account, item.debit, item.credit, width = width);
+                }
+                println!(
+                    "{:<width$}  {:>16}  {:>16}",
+                    "Total",
+                    trial_balance.total_debit,
+                    trial_balance.total_credit,
+                    width = width
+                );
+                if !trial_balance.is_balanced() {
+                    error!(
+                        "ledger is unbalanced: total debit {} != total credit {}",
+                        trial_balance.total_debit, trial_balance.total_credit
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
 impl Opts {
     pub async fn run(self) {
         match self {
@@ -138,6 +325,10 @@ impl Opts {
                 .await
                 .expect("cannot serve")
             }
+            Opts::Report(opts) => opts.report.run(),
+            Opts::Print(opts) => opts.run(),
+            Opts::Flatten(opts) => opts.run(),
+            Opts::CloseBooks(opts) => opts.run(),
             Opts::Update { verbose } => {
                 info!("performing self update");
                 info!("current version is {}", env!("ZHANG_BUILD_VERSION"));
@@ -181,6 +372,8 @@ async fn main() {
 #[cfg(test)]
 mod test {
     use std::io::{stdout, Write};
+    use std::path::Path;
+    use std::str::FromStr;
     use std::sync::Arc;
 
     use axum::body::Body;
@@ -209,6 +402,20 @@ mod test {
 
     };
 }
+    /// copy the test case into a temp folder, to avoid mutation requests modifying the original files
+    fn copy_dir(from: &Path, to: &Path) {
+        std::fs::create_dir_all(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            let target = to.join(entry.file_name());
+            if entry.path().is_dir() {
+                copy_dir(&entry.path(), &target);
+            } else {
+                std::fs::copy(entry.path(), target).unwrap();
+            }
+        }
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     async fn integration_test() {
         env_logger::try_init().ok();
@@ -216,7 +423,18 @@ mod test {
         #[derive(Deserialize)]
         struct Validation {
             uri: String,
+            #[serde(default)]
+            method: Option<String>,
+            #[serde(default)]
+            body: Option<Value>,
+            /// the expected status code of response, which is 200 by default
+            #[serde(default)]
+            status: Option<u16>,
+            #[serde(default)]
             validations: Vec<ValidationPoint>,
+            /// the lines expected in response body, which is used for non-json response
+            #[serde(default)]
+            contains: Vec<String>,
         }
         let paths = std::fs::read_dir("../integration-tests").unwrap();
 
@@ -227,7 +445,9 @@ mod test {
             }
             pprintln!("    \x1b[0;32mIntegration Test\x1b[0;0m: {}", path.path().display());
 
-            let pathbuf = path.path();
+            let temp_dir = tempfile::tempdir().unwrap();
+            copy_dir(&path.path(), temp_dir.path());
+            let pathbuf = temp_dir.path().to_path_buf();
             let validations_content = std::fs::read_to_string(path.path().join("validations.json")).unwrap();
             let validations: Vec<Validation> = serde_json::from_str(&validations_content).unwrap();
 
@@ -257,21 +477,43 @@ mod test {
                 let reload_sender = Arc::new(ReloadSender(tx));
                 let app = create_server_app(ledger_data, broadcaster, reload_sender, None);
 
+                let method = validation
+                    .method
+                    .as_deref()
+                    .map(|it| http::Method::from_str(it).expect("invalid http method"))
+                    .unwrap_or(http::Method::GET);
+                let body = validation.body.map(|it| Body::from(it.to_string())).unwrap_or_else(Body::empty);
                 let response = app
                     .oneshot(
                         Request::builder()
-                            .method(http::Method::GET)
+                            .method(method)
                             .uri(&validation.uri)
                             .header(http::header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
-                            .body(Body::empty())
+                            .body(body)
                             .unwrap(),
                     )
                     .await
                     .unwrap();
 
-                assert_eq!(response.status(), StatusCode::OK);
+                let expected_status = validation
+                    .status
+                    .map(|it| StatusCode::from_u16(it).expect("invalid status code"))
+                    .unwrap_or(StatusCode::OK);
+                assert_eq!(response.status(), expected_status);
 
                 let body = response.into_body().collect().await.unwrap().to_bytes();
+                if !validation.contains.is_empty() {
+                    let text = String::from_utf8(body.to_vec()).unwrap();
+                    for line in validation.contains {
+                        pprintln!("        \x1b[0;32mValidating\x1b[0;0m: response contains \x1b[0;34m{}\x1b[0;0m", line);
+                        if !text.lines().any(|it| it.eq(&line)) {
+                            panic!("Validation fail: response does not contain line {}\n{}", &line, &text);
+                        }
+                    }
+                }
+                if validation.validations.is_empty() {
+                    continue;
+                }
                 let res: Value = serde_json::from_slice(&body).unwrap();
 
                 for point in validation.validations {
@@ -1,5 +1,5 @@
 use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use async_recursion::async_recursion;
@@ -12,6 +12,7 @@ use zhang_core::data_source::{DataSource, LoadResult};
 use zhang_core::data_type::text::parser::parse as zhang_parse;
 use zhang_core::data_type::text::ZhangDataType;
 use zhang_core::data_type::DataType;
+use zhang_core::error::IoErrorIntoZhangError;
 use zhang_core::ledger::Ledger;
 use zhang_core::utils::has_path_visited;
 use zhang_core::{utils, ZhangError, ZhangResult};
@@ -89,7 +90,11 @@ impl DataSource for OpendalDataSource {
         info!("[opendal] save content path={}", &path);
         let vec = content.to_vec();
 
-        self.operator.write(&path, vec).await.expect("cannot write");
+        self.operator
+            .write(&path, vec)
+            .await
+            .map_err(std::io::Error::from)
+            .with_path(Path::new(&path))?;
         Ok(())
     }
 }
@@ -104,19 +109,23 @@ impl OpendalDataSource {
         } else if let Some(datetime) = directive.datetime() {
             let folder = datetime.format("data/%Y/").to_string();
 
-            self.operator.create_dir(&folder).await.expect("cannot create dir");
+            self.operator
+                .create_dir(&folder)
+                .await
+                .map_err(std::io::Error::from)
+                .with_path(Path::new(&folder))?;
 
             let path = format!("data/{}.zhang", datetime.format("%Y/%m"));
             entry.join(PathBuf::from(path))
         } else {
             entry.join(main_file_endpoint)
         };
-        let striped_endpoint = endpoint.strip_prefix(entry).expect("cannot strip entry prefix");
+        let striped_endpoint = endpoint.strip_prefix(entry).unwrap_or(&endpoint);
 
         if !has_path_visited(&ledger.visited_files, &endpoint) && check_file_visit {
             let path = match endpoint.strip_prefix(entry) {
-                Ok(relative_path) => relative_path.to_str().unwrap(),
-                Err(_) => endpoint.to_str().unwrap(),
+                Ok(relative_path) => relative_path.to_string_lossy(),
+                Err(_) => endpoint.to_string_lossy(),
             };
             self.append_directive(
                 ledger,
@@ -144,7 +153,10 @@ impl OpendalDataSource {
         let operator = match source {
             FileSystem::Fs => {
                 let mut builder = Fs::default();
-                builder.root(server_opts.path.to_string_lossy().to_string().as_str());
+                let root = server_opts.path.to_string_lossy().to_string();
+                builder.root(root.as_str());
+                // write to temp file and then rename it, to avoid corrupted files when writing fails halfway
+                builder.atomic_write_dir(root.as_str());
                 // Operator::new(builder).unwrap().finish()
                 Operator::new(builder).unwrap().finish()
             }
@@ -201,9 +213,7 @@ impl OpendalDataSource {
         Ok(directives)
     }
     async fn get_file_content(&self, path: PathBuf) -> ZhangResult<String> {
-        let path = path.to_str().expect("cannot convert path to string");
-
-        let vec = self.async_get(path.to_string()).await.expect("cannot read file");
-        Ok(String::from_utf8(vec).expect("invalid utf8 content"))
+        let vec = self.async_get(path.to_string_lossy().to_string()).await?;
+        Ok(String::from_utf8(vec)?)
     }
 }
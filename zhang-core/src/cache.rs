@@ -0,0 +1,248 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use sha256::digest;
+use zhang_ast::{Directive, Spanned};
+
+use crate::data_source::DataSource;
+use crate::error::IoErrorIntoZhangError;
+use crate::ledger::Ledger;
+use crate::utils::has_path_visited;
+use crate::ZhangResult;
+
+/// `LedgerCache` is the binary snapshot of processed directives, which is keyed by the content digest of all visited source files.
+/// Only local file system is supported, since the source files are read directly to validate the cache.
+#[derive(Serialize, Deserialize)]
+struct LedgerCache<D> {
+    entry: PathBuf,
+    endpoint: String,
+    files: Vec<(PathBuf, String)>,
+    directives: Vec<D>,
+}
+
+fn content_digest(path: &Path) -> ZhangResult<String> {
+    std::fs::read(path).map(digest).with_path(path)
+}
+
+impl LedgerCache<Spanned<Directive>> {
+    /// read the cache file, return `None` if cache file does not exist, is broken, or is outdated.
+    fn read(cache_file: &Path, entry: &Path, endpoint: &str) -> Option<Self> {
+        let content = std::fs::read(cache_file).ok()?;
+        let cache: Self = match bincode::deserialize(&content) {
+            Ok(cache) => cache,
+            Err(e) => {
+                warn!("cannot deserialize ledger cache {}, ignore it: {}", cache_file.display(), e);
+                return None;
+            }
+        };
+        if cache.entry.ne(entry) || cache.endpoint.ne(endpoint) {
+            return None;
+        }
+        if !cache.is_fresh() {
+            debug!("ledger cache {} is outdated", cache_file.display());
+            return None;
+        }
+        Some(cache)
+    }
+
+    /// re-resolve the include set from the main endpoint, and check every included file is the same as the cached one.
+    /// the includes of a file are taken from cached directives, which is safe since the file content is not changed.
+    fn is_fresh(&self) -> bool {
+        let Ok(main_endpoint) = self.entry.join(&self.endpoint).canonicalize() else {
+            return false;
+        };
+        let mut load_queue = VecDeque::from([main_endpoint]);
+        let mut visited: Vec<PathBuf> = vec![];
+        while let Some(path) = load_queue.pop_front() {
+            if has_path_visited(&visited, &path) {
+                continue;
+            }
+            let Some((_, cached_digest)) = self.files.iter().find(|(cached_path, _)| cached_path.eq(&path)) else {
+                return false;
+            };
+            if !content_digest(&path).map(|digest| digest.eq(cached_digest)).unwrap_or(false) {
+                return false;
+            }
+            self.directives
+                .iter()
+                .filter(|directive| directive.span.filename.as_ref() == Some(&path))
+                .filter_map(|directive| match &directive.data {
+                    Directive::Include(include) => Some(include.file.clone().to_plain_string()),
+                    _ => None,
+                })
+                .for_each(|file| {
+                    let full_path = if file.starts_with('/') {
+                        PathBuf::from(&file)
+                    } else {
+                        path.parent().map(|it| it.join(&file)).unwrap_or_else(|| PathBuf::from(&file))
+                    };
+                    load_queue.push_back(full_path);
+                });
+            visited.push(path);
+        }
+        visited.len() == self.files.len()
+    }
+}
+
+impl<'a> LedgerCache<&'a Spanned<Directive>> {
+    fn write(ledger: &'a Ledger, cache_file: &Path) -> ZhangResult<()> {
+        let (entry, endpoint) = &ledger.entry;
+        let files = ledger
+            .visited_files
+            .iter()
+            .map(|path| content_digest(path).map(|digest| (path.clone(), digest)))
+            .collect::<ZhangResult<Vec<_>>>()?;
+        // metas are stored in reversed order in ledger, restore it to keep the option overriding order
+        let directives = ledger.metas.iter().rev().chain(ledger.directives.iter()).collect();
+        let cache = LedgerCache {
+            entry: entry.clone(),
+            endpoint: endpoint.clone(),
+            files,
+            directives,
+        };
+        let content = bincode::serialize(&cache).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(cache_file, content).with_path(cache_file)
+    }
+}
+
+impl Ledger {
+    /// load ledger with a binary cache file, parsing is skipped if the include set and the content of all source files are not changed since the cache was written.
+    ///
+    /// The cache is meant for one-shot read-only commands like `print` and `report trial-balance`.
+    /// The server does not use it, since it mutates the ledger and reloads it on file changes.
+    pub fn load_with_cache(entry: PathBuf, endpoint: String, data_source: Arc<dyn DataSource>, cache_file: PathBuf) -> ZhangResult<Ledger> {
+        let entry = entry.canonicalize().with_path(&entry)?;
+
+        if let Some(cache) = LedgerCache::read(&cache_file, &entry, &endpoint) {
+            info!("load ledger from cache {}", cache_file.display());
+            let visited_files = cache.files.into_iter().map(|(path, _)| path).collect();
+            return Ledger::process(cache.directives, (entry, endpoint), visited_files, data_source);
+        }
+
+        let ledger = Ledger::load_with_data_source(entry, endpoint, data_source)?;
+        if let Err(e) = LedgerCache::write(&ledger, &cache_file) {
+            warn!("cannot write ledger cache {}: {}", cache_file.display(), e);
+        }
+        Ok(ledger)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use indoc::indoc;
+    use tempfile::tempdir;
+
+    use crate::data_source::{DataSource, LoadResult, LocalFileSystemDataSource};
+    use crate::data_type::text::ZhangDataType;
+    use crate::ledger::Ledger;
+    use crate::ZhangResult;
+
+    struct CountingDataSource {
+        inner: LocalFileSystemDataSource,
+        load_times: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl DataSource for CountingDataSource {
+        fn get(&self, path: String) -> ZhangResult<Vec<u8>> {
+            self.inner.get(path)
+        }
+        fn load(&self, entry: String, endpoint: String) -> ZhangResult<LoadResult> {
+            self.load_times.fetch_add(1, Ordering::SeqCst);
+            self.inner.load(entry, endpoint)
+        }
+    }
+
+    const CONTENT: &str = indoc! {r#"
+        option "title" "Cached"
+        1970-01-01 open Assets:Bank
+        1970-01-01 open Expenses:Food
+
+        2023-01-01 "KFC" "Crazy Thursday"
+          Assets:Bank -50 CNY
+          Expenses:Food
+    "#};
+
+    #[test]
+    fn should_hit_cache_given_second_load() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?.into_path();
+        std::fs::write(temp_dir.join("main.zhang"), CONTENT)?;
+        let cache_file = temp_dir.join("ledger.cache");
+        let data_source = Arc::new(CountingDataSource {
+            inner: LocalFileSystemDataSource::new(ZhangDataType {}),
+            load_times: AtomicUsize::new(0),
+        });
+
+        let first = Ledger::load_with_cache(temp_dir.clone(), "main.zhang".to_owned(), data_source.clone(), cache_file.clone())?;
+        assert!(cache_file.exists());
+        let second = Ledger::load_with_cache(temp_dir, "main.zhang".to_owned(), data_source.clone(), cache_file)?;
+
+        assert_eq!(1, data_source.load_times.load(Ordering::SeqCst));
+        assert_eq!(first.directives, second.directives);
+        assert_eq!(1, second.operations().transaction_counts()?);
+        assert_eq!("Cached", second.operations().option("title")?.unwrap().value);
+        assert_eq!(first.visited_files, second.visited_files);
+        Ok(())
+    }
+
+    #[test]
+    fn should_invalidate_cache_given_source_file_modified() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?.into_path();
+        let main_file = temp_dir.join("main.zhang");
+        std::fs::write(&main_file, CONTENT)?;
+        let cache_file = temp_dir.join("ledger.cache");
+        let data_source = Arc::new(CountingDataSource {
+            inner: LocalFileSystemDataSource::new(ZhangDataType {}),
+            load_times: AtomicUsize::new(0),
+        });
+
+        Ledger::load_with_cache(temp_dir.clone(), "main.zhang".to_owned(), data_source.clone(), cache_file.clone())?;
+
+        std::fs::write(
+            &main_file,
+            format!("{}\n2023-01-02 \"KFC\" \"Crazy Thursday\"\n  Assets:Bank -50 CNY\n  Expenses:Food\n", CONTENT),
+        )?;
+
+        let ledger = Ledger::load_with_cache(temp_dir, "main.zhang".to_owned(), data_source.clone(), cache_file)?;
+
+        assert_eq!(2, data_source.load_times.load(Ordering::SeqCst));
+        assert_eq!(2, ledger.operations().transaction_counts()?);
+        Ok(())
+    }
+
+    #[test]
+    fn should_invalidate_cache_given_new_file_included_with_modified_time_kept() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?.into_path();
+        let main_file = temp_dir.join("main.zhang");
+        std::fs::write(&main_file, CONTENT)?;
+        let modified_time = std::fs::metadata(&main_file)?.modified()?;
+        let cache_file = temp_dir.join("ledger.cache");
+        let data_source = Arc::new(CountingDataSource {
+            inner: LocalFileSystemDataSource::new(ZhangDataType {}),
+            load_times: AtomicUsize::new(0),
+        });
+
+        Ledger::load_with_cache(temp_dir.clone(), "main.zhang".to_owned(), data_source.clone(), cache_file.clone())?;
+
+        std::fs::write(
+            temp_dir.join("2023.zhang"),
+            "2023-01-02 \"KFC\" \"Crazy Thursday\"\n  Assets:Bank -50 CNY\n  Expenses:Food\n",
+        )?;
+        std::fs::write(&main_file, format!("{}\ninclude \"2023.zhang\"\n", CONTENT))?;
+        // tools like `git checkout` or `rsync -t` may keep the modified time of source files
+        std::fs::File::options().write(true).open(&main_file)?.set_modified(modified_time)?;
+
+        let ledger = Ledger::load_with_cache(temp_dir, "main.zhang".to_owned(), data_source.clone(), cache_file)?;
+
+        assert_eq!(2, data_source.load_times.load(Ordering::SeqCst));
+        assert_eq!(2, ledger.operations().transaction_counts()?);
+        assert_eq!(2, ledger.visited_files.len());
+        Ok(())
+    }
+}
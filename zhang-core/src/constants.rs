@@ -1,17 +1,26 @@
-use zhang_ast::Rounding;
+use zhang_ast::{BookingMethod, Rounding};
 
 pub const KEY_OPERATING_CURRENCY: &str = "operating_currency";
 pub const KEY_DEFAULT_ROUNDING: &str = "default_rounding";
 pub const KEY_DEFAULT_BALANCE_TOLERANCE_PRECISION: &str = "default_balance_tolerance_precision";
 pub const KEY_DEFAULT_COMMODITY_PRECISION: &str = "default_commodity_precision";
 pub const KEY_TIMEZONE: &str = "timezone";
+pub const KEY_DEFAULT_COMMODITY: &str = "default_commodity";
+pub const KEY_INVENTORY_BOOKING: &str = "inventory_booking";
+pub const KEY_CURRENCY_SYMBOL: &str = "currency_symbol";
+pub const KEY_CLOSED_THROUGH: &str = "closed_through";
+pub const KEY_DOCUMENTS_PATH: &str = "documents_path";
+pub const KEY_PRICE_STALENESS_DAYS: &str = "price_staleness_days";
 
 pub const DEFAULT_COMMODITY_PRECISION: i32 = 2;
 pub const DEFAULT_OPERATING_CURRENCY: &str = "CNY";
 pub const DEFAULT_ROUNDING: Rounding = Rounding::RoundDown;
 pub const DEFAULT_BALANCE_TOLERANCE_PRECISION: i32 = 2;
 pub const DEFAULT_TIMEZONE: &str = "Asia/Hong_Kong";
+pub const DEFAULT_BOOKING_METHOD: BookingMethod = BookingMethod::Fifo;
+pub const DEFAULT_PRICE_STALENESS_DAYS: i64 = 30;
 
 pub const DEFAULT_ROUNDING_PLAIN: &str = "RoundDown";
 pub const DEFAULT_COMMODITY_PRECISION_PLAIN: &str = "2";
 pub const DEFAULT_BALANCE_TOLERANCE_PRECISION_PLAIN: &str = "2";
+pub const DEFAULT_BOOKING_METHOD_PLAIN: &str = "FIFO";
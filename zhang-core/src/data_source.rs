@@ -1,8 +1,10 @@
 use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use chrono::Datelike;
 use log::debug;
+use uuid::Uuid;
 use zhang_ast::{Directive, Include, SpanInfo, Spanned, ZhangString};
 
 use crate::data_type::DataType;
@@ -69,8 +71,34 @@ impl LocalFileSystemDataSource {
         }
     }
 
-    pub(crate) fn create_folder_if_not_exist(filename: &std::path::Path) {
-        std::fs::create_dir_all(filename.parent().unwrap()).expect("cannot create folder recursive");
+    pub(crate) fn create_folder_if_not_exist(filename: &Path) -> ZhangResult<()> {
+        if let Some(parent) = filename.parent() {
+            std::fs::create_dir_all(parent).with_path(parent)?;
+        }
+        Ok(())
+    }
+
+    /// write content into a temp file in the same folder, and then rename it to the target path.
+    /// the target file is either untouched or fully written, even if the writing fails halfway.
+    /// the temp file name is unique, so that concurrent writings do not override each other's temp file.
+    fn atomic_write(path: &Path, content: &[u8]) -> ZhangResult<()> {
+        LocalFileSystemDataSource::atomic_write_with(path, |file| file.write_all(content))
+    }
+
+    fn atomic_write_with(path: &Path, write: impl FnOnce(&mut std::fs::File) -> std::io::Result<()>) -> ZhangResult<()> {
+        let file_name = path.file_name().map(|it| it.to_string_lossy().to_string()).unwrap_or_default();
+        let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, Uuid::new_v4()));
+        let result = std::fs::File::create(&temp_path)
+            .and_then(|mut file| {
+                write(&mut file)?;
+                file.sync_all()
+            })
+            .with_path(&temp_path)
+            .and_then(|_| std::fs::rename(&temp_path, path).with_path(path));
+        if result.is_err() && temp_path.is_file() {
+            let _ = std::fs::remove_file(&temp_path);
+        }
+        result
     }
 
     fn append_directive(&self, ledger: &Ledger, directive: Directive, file: Option<PathBuf>, check_file_visit: bool) -> ZhangResult<()> {
@@ -84,12 +112,12 @@ impl LocalFileSystemDataSource {
             }
         });
 
-        LocalFileSystemDataSource::create_folder_if_not_exist(&endpoint);
+        LocalFileSystemDataSource::create_folder_if_not_exist(&endpoint)?;
 
         if !has_path_visited(&ledger.visited_files, &endpoint) && check_file_visit {
             let path = match endpoint.strip_prefix(entry) {
-                Ok(relative_path) => relative_path.to_str().unwrap(),
-                Err(_) => endpoint.to_str().unwrap(),
+                Ok(relative_path) => relative_path.to_string_lossy(),
+                Err(_) => endpoint.to_string_lossy(),
             };
             self.append_directive(
                 ledger,
@@ -101,8 +129,11 @@ impl LocalFileSystemDataSource {
             )?;
         }
 
-        let content_buf = ledger.data_source.get(endpoint.to_string_lossy().to_string())?;
-        let content = String::from_utf8(content_buf)?;
+        let content = if endpoint.exists() {
+            String::from_utf8(ledger.data_source.get(endpoint.to_string_lossy().to_string())?)?
+        } else {
+            String::new()
+        };
 
         let appended_content = format!("{}\n{}\n", content, self.data_type.export(Spanned::new(directive, SpanInfo::default())));
 
@@ -160,7 +191,7 @@ impl DataSource for LocalFileSystemDataSource {
     }
 
     fn save(&self, _ledger: &Ledger, path: String, content: &[u8]) -> ZhangResult<()> {
-        std::fs::write(&path, content).with_path(PathBuf::from(path).as_path())
+        LocalFileSystemDataSource::atomic_write(&PathBuf::from(path), content)
     }
 
     fn append(&self, ledger: &Ledger, directives: Vec<Directive>) -> ZhangResult<()> {
@@ -175,3 +206,145 @@ pub struct LoadResult {
     pub directives: Vec<Spanned<Directive>>,
     pub visited_files: Vec<PathBuf>,
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+    use indoc::indoc;
+    use tempfile::tempdir;
+    use zhang_ast::amount::Amount;
+    use zhang_ast::{Account, Date, Directive, Flag, Options, Posting, Transaction, ZhangString};
+
+    use crate::data_source::LocalFileSystemDataSource;
+    use crate::data_type::text::ZhangDataType;
+    use crate::ledger::Ledger;
+    use crate::ZhangError;
+
+    const CONTENT: &str = indoc! {r#"
+        option "title" "Atomic"
+        1970-01-01 open Assets:Bank
+    "#};
+
+    fn operating_currency_option() -> Directive {
+        Directive::Option(Options {
+            key: ZhangString::quote("operating_currency"),
+            value: ZhangString::quote("CNY"),
+        })
+    }
+
+    fn load(temp_dir: &Path, content: &str) -> Result<Ledger, Box<dyn std::error::Error>> {
+        std::fs::write(temp_dir.join("main.zhang"), content)?;
+        let data_source = Arc::new(LocalFileSystemDataSource::new(ZhangDataType {}));
+        Ok(Ledger::load_with_data_source(temp_dir.to_path_buf(), "main.zhang".to_owned(), data_source)?)
+    }
+
+    #[test]
+    fn should_append_directive_without_leaving_temp_file() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?.into_path();
+        let ledger = load(&temp_dir, CONTENT)?;
+
+        ledger.append(vec![operating_currency_option()])?;
+
+        let content = std::fs::read_to_string(temp_dir.join("main.zhang"))?;
+        assert!(content.starts_with(CONTENT));
+        assert!(content.contains("option \"operating_currency\" \"CNY\""));
+        assert!(temp_files(&temp_dir)?.is_empty());
+        Ok(())
+    }
+
+    fn temp_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        Ok(std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|it| it.path()))
+            .collect::<std::io::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|path| path.extension().map(|ext| ext == "tmp").unwrap_or(false))
+            .collect())
+    }
+
+    #[test]
+    fn should_keep_original_file_given_write_failed() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?.into_path();
+        let target = temp_dir.join("main.zhang");
+        std::fs::write(&target, CONTENT)?;
+
+        let result = LocalFileSystemDataSource::atomic_write_with(&target, |file| {
+            file.write_all(b"option \"title\" \"Bro")?;
+            Err(std::io::Error::other("disk is full"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(CONTENT.as_bytes(), std::fs::read(&target)?);
+        assert!(temp_files(&temp_dir)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn should_not_override_temp_file_given_concurrent_writes() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?.into_path();
+        let target = temp_dir.join("main.zhang");
+        let contents = (0..8).map(|idx| format!("{}; writer {}\n", CONTENT, idx).repeat(1000)).collect::<Vec<_>>();
+
+        std::thread::scope(|scope| {
+            for content in contents.iter() {
+                let target = &target;
+                scope.spawn(move || LocalFileSystemDataSource::atomic_write(target, content.as_bytes()).unwrap());
+            }
+        });
+
+        let written = std::fs::read_to_string(&target)?;
+        assert!(contents.contains(&written));
+        assert!(temp_files(&temp_dir)?.is_empty());
+        Ok(())
+    }
+
+    fn transaction(date: NaiveDate) -> Directive {
+        let posting = |account: &str, units: Option<Amount>| Posting {
+            flag: None,
+            account: Account::from_str(account).unwrap(),
+            units,
+            cost: None,
+            cost_date: None,
+            price: None,
+            comment: None,
+            meta: Default::default(),
+        };
+        Directive::Transaction(Transaction {
+            date: Date::Date(date),
+            flag: Some(Flag::Okay),
+            payee: Some(ZhangString::quote("KFC")),
+            narration: None,
+            tags: Default::default(),
+            links: Default::default(),
+            postings: vec![
+                posting("Assets:Bank", Some(Amount::new(BigDecimal::from(-50), "CNY"))),
+                posting("Expenses:Food", None),
+            ],
+            meta: Default::default(),
+        })
+    }
+
+    #[test]
+    fn should_reject_appending_directive_in_closed_period() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?.into_path();
+        let content = indoc! {r#"
+            option "closed_through" "2023-12-31"
+            1970-01-01 open Assets:Bank
+            1970-01-01 open Expenses:Food
+        "#};
+        let ledger = load(&temp_dir, content)?;
+
+        let result = ledger.append(vec![transaction(NaiveDate::from_ymd_opt(2023, 12, 31).unwrap())]);
+        assert!(matches!(result, Err(ZhangError::ClosedPeriod(..))));
+        assert!(!temp_dir.join("data/2023/12.zhang").exists());
+
+        ledger.append(vec![transaction(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())])?;
+        assert!(temp_dir.join("data/2024/1.zhang").exists());
+        Ok(())
+    }
+}
@@ -5,6 +5,8 @@ use zhang_ast::{Directive, Spanned};
 use crate::data_type::text::exporter::ZhangDataTypeExportable;
 use crate::data_type::text::parser::parse;
 use crate::data_type::DataType;
+use crate::error::IoErrorIntoZhangError;
+use crate::ledger::Ledger;
 use crate::{ZhangError, ZhangResult};
 
 #[allow(clippy::upper_case_acronyms)]
@@ -28,3 +30,95 @@ impl DataType for ZhangDataType {
         directive.data.export()
     }
 }
+
+impl ZhangDataType {
+    /// inline all visited files of ledger into one zhang text in load order. `include` directives are dropped,
+    /// while comments and formatting of each file are kept as is, and each file is led by a `; ===== file.zhang =====` banner.
+    pub fn flatten(&self, ledger: &Ledger) -> ZhangResult<String> {
+        let (entry, _) = &ledger.entry;
+        let mut sections = vec![];
+        for file in &ledger.visited_files {
+            let path = file.to_string_lossy().to_string();
+            let content = String::from_utf8(ledger.data_source.get(path.clone())?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+                .with_path(file)?;
+            let directives = self.transform(content.clone(), Some(path))?;
+
+            let mut flattened = String::with_capacity(content.len());
+            let mut last_end = 0;
+            for include in directives.iter().filter(|it| matches!(it.data, Directive::Include(_))) {
+                flattened.push_str(&content[last_end..include.span.start]);
+                // drop the line break of include line as well, to avoid leaving a blank line
+                let rest = &content[include.span.end..];
+                let line_break = ["\r\n", "\n"].into_iter().find(|it| rest.starts_with(it)).map(str::len).unwrap_or(0);
+                last_end = include.span.end + line_break;
+            }
+            flattened.push_str(&content[last_end..]);
+
+            let name = file.strip_prefix(entry).unwrap_or(file);
+            sections.push(format!("; ===== {} =====\n{}", name.display(), flattened.trim_end()));
+        }
+        Ok(format!("{}\n", sections.join("\n\n")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use indoc::indoc;
+    use tempfile::tempdir;
+    use zhang_ast::Directive;
+
+    use crate::data_source::LocalFileSystemDataSource;
+    use crate::data_type::text::ZhangDataType;
+    use crate::data_type::DataType;
+    use crate::ledger::Ledger;
+
+    #[test]
+    fn should_flatten_included_files_in_load_order() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?.into_path();
+        let main = indoc! {r#"
+            option "title" "Flatten"
+            ; accounts are defined in another file
+            include "accounts.zhang"
+
+            2023-01-01 "KFC" "Crazy Thursday"
+              Assets:Bank -50 CNY
+              Expenses:Food
+        "#};
+        let accounts = indoc! {r#"
+            ; bank accounts
+            1970-01-01 open Assets:Bank
+            1970-01-01 open Expenses:Food
+        "#};
+        std::fs::write(temp_dir.join("main.zhang"), main)?;
+        std::fs::write(temp_dir.join("accounts.zhang"), accounts)?;
+        let ledger = Ledger::load_with_data_source(temp_dir, "main.zhang".to_owned(), Arc::new(LocalFileSystemDataSource::new(ZhangDataType {})))?;
+
+        let data_type = ZhangDataType {};
+        let flattened = data_type.flatten(&ledger)?;
+
+        assert!(!flattened.contains("include"));
+        assert!(flattened.starts_with("; ===== main.zhang =====\n"));
+        assert!(flattened.contains("; accounts are defined in another file"));
+        assert!(flattened.contains("; ===== accounts.zhang =====\n; bank accounts"));
+
+        let is_source_directive =
+            |it: &Directive| !matches!(it, Directive::Include(_)) && !matches!(it, Directive::Comment(comment) if comment.content.starts_with("====="));
+        let expected = [("main.zhang", main), ("accounts.zhang", accounts)]
+            .into_iter()
+            .flat_map(|(file, content)| data_type.transform(content.to_owned(), Some(file.to_owned())).unwrap())
+            .map(|it| it.data)
+            .filter(is_source_directive)
+            .collect::<Vec<_>>();
+        let actual = data_type
+            .transform(flattened, None)?
+            .into_iter()
+            .map(|it| it.data)
+            .filter(is_source_directive)
+            .collect::<Vec<_>>();
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+}
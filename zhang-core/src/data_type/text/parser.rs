@@ -26,6 +26,10 @@ impl ZhangParser {
     fn number(input: Node) -> Result<BigDecimal> {
         Ok(BigDecimal::from_str(input.as_str()).unwrap())
     }
+    /// number with thousands separators, e.g. `1,234.56`
+    fn grouped_number(input: Node) -> Result<BigDecimal> {
+        Ok(BigDecimal::from_str(&input.as_str().replace(',', "")).unwrap())
+    }
     fn quote_string(input: Node) -> Result<ZhangString> {
         let string = input.as_str();
         Ok(ZhangString::QuoteString(unescape(string).unwrap()))
@@ -170,18 +174,21 @@ impl ZhangParser {
 
     fn posting_cost(input: Node) -> Result<Amount> {
         let ret: Amount = match_nodes!(input.into_children();
+            [number(amount)] => Amount::new(amount, String::new()),
             [number(amount), commodity_name(c)] => Amount::new(amount, c),
         );
         Ok(ret)
     }
     fn posting_total_price(input: Node) -> Result<Amount> {
         let ret: Amount = match_nodes!(input.into_children();
+            [number(amount)] => Amount::new(amount, String::new()),
             [number(amount), commodity_name(c)] => Amount::new(amount, c),
         );
         Ok(ret)
     }
     fn posting_single_price(input: Node) -> Result<Amount> {
         let ret: Amount = match_nodes!(input.into_children();
+            [number(amount)] => Amount::new(amount, String::new()),
             [number(amount), commodity_name(c)] => Amount::new(amount, c),
         );
         Ok(ret)
@@ -189,7 +196,29 @@ impl ZhangParser {
 
     fn posting_amount(input: Node) -> Result<Amount> {
         let ret: Amount = match_nodes!(input.into_children();
+            [symbol_amount(amount)] => amount,
+            [number(amount)] => Amount::new(amount, String::new()),
             [number(amount), commodity_name(c)] => Amount::new(amount, c),
+            [grouped_number(amount)] => Amount::new(amount, String::new()),
+            [grouped_number(amount), commodity_name(c)] => Amount::new(amount, c),
+        );
+        Ok(ret)
+    }
+
+    fn amount_sign(_input: Node) -> Result<()> {
+        Ok(())
+    }
+    fn currency_symbol(input: Node) -> Result<String> {
+        Ok(input.as_str().to_owned())
+    }
+
+    /// amount led by currency symbol, e.g. `-$1,234.56`, the symbol is mapped to commodity by option `currency_symbol` when processing
+    fn symbol_amount(input: Node) -> Result<Amount> {
+        let ret: Amount = match_nodes!(input.into_children();
+            [currency_symbol(symbol), number(amount)] => Amount::new(amount, symbol),
+            [currency_symbol(symbol), grouped_number(amount)] => Amount::new(amount, symbol),
+            [amount_sign(_), currency_symbol(symbol), number(amount)] => Amount::new(-amount, symbol),
+            [amount_sign(_), currency_symbol(symbol), grouped_number(amount)] => Amount::new(-amount, symbol),
         );
         Ok(ret)
     }
@@ -206,6 +235,10 @@ impl ZhangParser {
         Ok(ret)
     }
     fn posting_meta(input: Node) -> Result<(Option<Amount>, Option<Date>, Option<SingleTotalPrice>)> {
+        // price chain like `10 USD @ 7 CNY @ 0.9 EUR` is not supported, reject it instead of ignoring the rest prices
+        if let Some(chained_price) = input.children().find(|it| it.as_rule() == Rule::chained_price) {
+            return Err(chained_price.error("only one price is allowed in a posting, chained price via multiple `@` is not supported"));
+        }
         let ret: (Option<Amount>, Option<Date>, Option<SingleTotalPrice>) = match_nodes!(input.into_children();
             [] => (None, None, None),
             [posting_cost(cost)] => (Some(cost), None, None),
@@ -412,7 +445,9 @@ impl ZhangParser {
 
     fn balance(input: Node) -> Result<Directive> {
         let ret: (Date, Account, BigDecimal, String, Option<Account>) = match_nodes!(input.into_children();
+            [date(date), account_name(name), number(amount)] => (date, name, amount, String::new(), None),
             [date(date), account_name(name), number(amount), commodity_name(commodity)] => (date, name, amount, commodity, None),
+            [date(date), account_name(name), number(amount), account_name(pad)] => (date, name, amount, String::new(), Some(pad)),
             [date(date), account_name(name), number(amount), commodity_name(commodity), account_name(pad)] => (date, name, amount, commodity, Some(pad)),
         );
         if let Some(pad) = ret.4 {
@@ -449,6 +484,7 @@ impl ZhangParser {
 
     fn price(input: Node) -> Result<Directive> {
         let ret: (Date, String, BigDecimal, String) = match_nodes!(input.into_children();
+            [date(date), commodity_name(source), number(price)] => (date, source, price, String::new()),
             [date(date), commodity_name(source), number(price), commodity_name(target)] => (date, source, price, target)
         );
         Ok(Directive::Price(Price {
@@ -673,6 +709,21 @@ mod test {
                 balance.data
             )
         }
+
+        #[test]
+        fn should_parse_balance_pad_without_commodity() {
+            let balance = parse("2101-10-10 balance Assets:Hello 123 with pad Income:Earnings", None).unwrap().remove(0);
+            assert_eq!(
+                Directive::BalancePad(BalancePad {
+                    date: Date::Date(NaiveDate::from_ymd_opt(2101, 10, 10).unwrap()),
+                    account: Account::from_str("Assets:Hello").unwrap(),
+                    amount: Amount::new(BigDecimal::from(123i32), ""),
+                    pad: Account::from_str("Income:Earnings").unwrap(),
+                    meta: Default::default()
+                }),
+                balance.data
+            )
+        }
     }
     mod options {
 
@@ -878,6 +929,8 @@ mod test {
         }
 
         mod posting {
+            use std::str::FromStr;
+
             use bigdecimal::{BigDecimal, FromPrimitive};
             use chrono::NaiveDate;
             use indoc::indoc;
@@ -928,6 +981,64 @@ mod test {
                 assert_eq!(None, posting.price);
             }
             #[test]
+            fn should_return_unit_without_commodity_given_bare_number() {
+                let mut trx = get_first_posting(indoc! {r#"
+                2022-06-02 "balanced transaction"
+                  Assets:Card -100
+                "#});
+                let posting = trx.postings.pop().unwrap();
+                assert_eq!(Some(Amount::new(BigDecimal::from(-100i32), "")), posting.units);
+                assert_eq!(None, posting.cost);
+                assert_eq!(None, posting.price);
+            }
+            #[test]
+            fn should_return_unit_given_sign_currency_symbol_and_thousands_separator() {
+                let mut trx = get_first_posting(indoc! {r#"
+                option "currency_symbol" "$:USD,¥:CNY"
+                2022-06-02 "balanced transaction"
+                  Assets:Card -$1,234.56
+                "#});
+                let posting = trx.postings.pop().unwrap();
+                assert_eq!(Some(Amount::new(BigDecimal::from_str("-1234.56").unwrap(), "$")), posting.units);
+                assert_eq!(None, posting.cost);
+                assert_eq!(None, posting.price);
+            }
+            #[test]
+            fn should_return_unit_given_currency_symbol() {
+                let mut trx = get_first_posting(indoc! {r#"
+                option "currency_symbol" "$:USD,¥:CNY"
+                2022-06-02 "balanced transaction"
+                  Assets:Card $10
+                  Assets:Cash ¥100
+                "#});
+                let cash = trx.postings.pop().unwrap();
+                assert_eq!(Some(Amount::new(BigDecimal::from(100i32), "¥")), cash.units);
+                let card = trx.postings.pop().unwrap();
+                assert_eq!(Some(Amount::new(BigDecimal::from(10i32), "$")), card.units);
+            }
+            #[test]
+            fn should_return_unit_given_thousands_separator_with_commodity() {
+                let mut trx = get_first_posting(indoc! {r#"
+                2022-06-02 "balanced transaction"
+                  Assets:Card -1,234,567.8 USD
+                "#});
+                let posting = trx.postings.pop().unwrap();
+                assert_eq!(Some(Amount::new(BigDecimal::from_str("-1234567.8").unwrap(), "USD")), posting.units);
+            }
+            #[test]
+            fn should_raise_error_given_chained_price() {
+                let result = parse(
+                    indoc! {r#"
+                    2022-06-02 "balanced transaction"
+                      Assets:Card 10 USD @ 7 CNY @ 0.9 EUR
+                    "#},
+                    None,
+                );
+                let error = result.unwrap_err().to_string();
+                assert!(error.contains("only one price is allowed in a posting"));
+                assert!(error.contains("2:29"));
+            }
+            #[test]
             fn should_return_unit_and_cost() {
                 let mut trx = get_first_posting(indoc! {r#"
                 2022-06-02 "balanced transaction"
@@ -953,6 +1064,16 @@ mod test {
                 assert_eq!(None, posting.price);
             }
             #[test]
+            fn should_return_cost_with_explicit_plus_sign() {
+                let mut trx = get_first_posting(indoc! {r#"
+                2022-06-02 "cost adjustment"
+                  Assets:Broker 0 AAPL {+5 USD}
+                "#});
+                let posting = trx.postings.pop().unwrap();
+                assert_eq!(Some(Amount::new(BigDecimal::from(0i32), "AAPL")), posting.units);
+                assert_eq!(Some(Amount::new(BigDecimal::from(5i32), "USD")), posting.cost);
+            }
+            #[test]
             fn should_return_unit_and_single_price() {
                 let mut trx = get_first_posting(indoc! {r#"
                 2022-06-02 "balanced transaction"
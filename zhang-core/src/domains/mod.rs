@@ -1,25 +1,31 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
-use std::ops::{Add, AddAssign, Sub};
+use std::ops::{Add, AddAssign, Div, Sub, SubAssign};
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use bigdecimal::{BigDecimal, Zero};
-use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use bigdecimal::{BigDecimal, Signed, Zero};
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use chrono_tz::Tz;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use serde::Deserialize;
 use uuid::Uuid;
 use zhang_ast::amount::Amount;
-use zhang_ast::{Account, AccountType, Currency, Date, Flag, Meta, SpanInfo};
+use zhang_ast::{Account, AccountType, BookingMethod, Currency, Date, Flag, Meta, SpanInfo};
 
+use crate::constants::{DEFAULT_OPERATING_CURRENCY, DEFAULT_PRICE_STALENESS_DAYS, KEY_OPERATING_CURRENCY, KEY_PRICE_STALENESS_DAYS};
 use crate::domains::schemas::{
-    AccountBalanceDomain, AccountDailyBalanceDomain, AccountDomain, AccountJournalDomain, AccountStatus, CommodityDomain, ErrorDomain, ErrorType, MetaDomain,
-    MetaType, OptionDomain, PriceDomain, TransactionInfoDomain,
+    AccountBalanceDomain, AccountDailyBalanceDomain, AccountDomain, AccountJournalDomain, AccountStatus, AverageMonthlySpendDomain, BalanceSheetDomain,
+    CommodityDomain, ErrorDomain, ErrorType, IncomeStatementDomain, MetaDomain, MetaType, OptionDomain, PriceDomain, StalePriceDomain, StatementItemDomain,
+    StatementSectionDomain, TransactionInfoDomain, TrialBalanceDomain, TrialBalanceItemDomain,
 };
 use crate::store::{
-    BudgetDomain, BudgetEvent, BudgetEventType, BudgetIntervalDetail, CommodityLotRecord, DocumentDomain, DocumentType, PostingDomain, Store, TransactionDomain,
+    BudgetDomain, BudgetEvent, BudgetEventType, BudgetIntervalDetail, BudgetRollupAmount, BudgetRollupDetail, CommodityLotRecord, DocumentDomain, DocumentType,
+    PostingDomain, RealizedGainDomain, Store, TransactionDomain,
 };
+use crate::utils::calculable::Calculable;
+use crate::utils::local_datetime;
 use crate::{ZhangError, ZhangResult};
 
 pub mod schemas;
@@ -89,10 +95,10 @@ impl Operations {
 }
 
 impl Operations {
-    pub fn read(&self) -> RwLockReadGuard<Store> {
+    pub fn read(&self) -> RwLockReadGuard<'_, Store> {
         self.store.read().unwrap()
     }
-    pub fn write(&self) -> RwLockWriteGuard<Store> {
+    pub fn write(&self) -> RwLockWriteGuard<'_, Store> {
         self.store.write().unwrap()
     }
 }
@@ -100,7 +106,9 @@ impl Operations {
 impl Operations {
     /// insert or update account
     /// if account exists, then update its status only
-    pub(crate) fn insert_or_update_account(&mut self, datetime: DateTime<Tz>, account: Account, status: AccountStatus, alias: Option<&str>) -> ZhangResult<()> {
+    pub(crate) fn insert_or_update_account(
+        &mut self, datetime: DateTime<Tz>, account: Account, status: AccountStatus, alias: Option<&str>, defined_in: Option<PathBuf>,
+    ) -> ZhangResult<()> {
         let mut store = self.write();
         let account_domain = store.accounts.entry(account.name().to_owned()).or_insert_with(|| AccountDomain {
             date: datetime.naive_local(),
@@ -108,6 +116,7 @@ impl Operations {
             name: account.name().to_owned(),
             status,
             alias: alias.map(|it| it.to_owned()),
+            defined_in,
         });
 
         // if account exists, the property only can be changed is status;
@@ -120,7 +129,7 @@ impl Operations {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn insert_transaction(
         &mut self, id: &Uuid, sequence: i32, datetime: DateTime<Tz>, flag: Flag, payee: Option<&str>, narration: Option<&str>, tags: Vec<String>,
-        links: Vec<String>, span: &SpanInfo,
+        links: Vec<String>, rounding_residual: Option<Vec<Amount>>, span: &SpanInfo,
     ) -> ZhangResult<()> {
         let mut store = self.write();
 
@@ -136,6 +145,7 @@ impl Operations {
                 span: span.clone(),
                 tags,
                 links,
+                rounding_residual,
                 postings: vec![],
             },
         );
@@ -226,11 +236,18 @@ impl Operations {
         }))
     }
 
-    pub(crate) fn account_lot(&mut self, account_name: &str, currency: &str, price: Option<Amount>) -> ZhangResult<Option<CommodityLotRecord>> {
+    /// find the lot of given commodity by its cost and acquisition datetime
+    pub(crate) fn account_lot(
+        &mut self, account_name: &str, currency: &str, price: Option<Amount>, datetime: Option<DateTime<Tz>>,
+    ) -> ZhangResult<Option<CommodityLotRecord>> {
         let mut store = self.write();
         let entry = store.commodity_lots.entry(account_name.to_owned()).or_default();
 
-        let option = entry.iter().filter(|lot| lot.commodity.eq(currency)).find(|lot| lot.price.eq(&price)).cloned();
+        let option = entry
+            .iter()
+            .filter(|lot| lot.commodity.eq(currency))
+            .find(|lot| lot.price.eq(&price) && lot.datetime.eq(&datetime))
+            .cloned();
 
         Ok(option)
     }
@@ -247,17 +264,21 @@ impl Operations {
 
         Ok(option)
     }
-    pub(crate) fn update_account_lot(&mut self, account_name: &str, currency: &str, price: Option<Amount>, amount: &BigDecimal) -> ZhangResult<()> {
+    pub(crate) fn update_account_lot(
+        &mut self, account_name: &str, currency: &str, price: Option<Amount>, datetime: Option<DateTime<Tz>>, amount: &BigDecimal,
+    ) -> ZhangResult<()> {
         let mut store = self.write();
         let entry = store.commodity_lots.entry(account_name.to_owned()).or_default();
 
-        let option = entry.iter_mut().find(|lot| lot.price.eq(&price));
+        let option = entry
+            .iter_mut()
+            .find(|lot| lot.commodity.eq(currency) && lot.price.eq(&price) && lot.datetime.eq(&datetime));
         if let Some(lot) = option {
             lot.amount = amount.clone();
         } else {
             entry.push(CommodityLotRecord {
                 commodity: currency.to_owned(),
-                datetime: None,
+                datetime,
                 amount: amount.clone(),
                 price,
             })
@@ -265,13 +286,98 @@ impl Operations {
         Ok(())
     }
 
-    pub(crate) fn insert_account_lot(&mut self, account_name: &str, currency: &str, price: Option<Amount>, amount: &BigDecimal) -> ZhangResult<()> {
+    /// reduce the lots with cost of given commodity in the order of booking method, lots are ordered by their acquisition datetime.
+    /// the reduced lots are returned with their costs and reduced numbers, and the number exceeding the held lots goes to the lot without cost.
+    pub(crate) fn reduce_account_lots(
+        &mut self, account_name: &str, currency: &str, number: &BigDecimal, booking_method: BookingMethod,
+    ) -> ZhangResult<Vec<(Amount, BigDecimal)>> {
+        let mut store = self.write();
+        let lots = store.commodity_lots.entry(account_name.to_owned()).or_default();
+
+        let mut remaining = number.clone();
+        let mut reduced_lots = vec![];
+        // stable sort keeps the booking order of lots acquired at the same datetime
+        lots.sort_by_key(|lot| lot.datetime);
+        let ordered_lots: Box<dyn Iterator<Item = &mut CommodityLotRecord>> = match booking_method {
+            BookingMethod::Fifo => Box::new(lots.iter_mut()),
+            BookingMethod::Lifo => Box::new(lots.iter_mut().rev()),
+        };
+        for lot in ordered_lots.filter(|lot| lot.commodity.eq(currency) && lot.price.is_some() && lot.amount.is_positive()) {
+            if remaining.is_zero() {
+                break;
+            }
+            let reduced_number = if lot.amount.lt(&remaining) { lot.amount.clone() } else { remaining.clone() };
+            lot.amount = (&lot.amount).sub(&reduced_number);
+            remaining = (&remaining).sub(&reduced_number);
+            reduced_lots.push((lot.price.clone().expect("lot with cost"), reduced_number));
+        }
+
+        if !remaining.is_zero() {
+            match lots.iter_mut().find(|lot| lot.commodity.eq(currency) && lot.price.is_none()) {
+                Some(lot) => lot.amount = (&lot.amount).sub(&remaining),
+                None => lots.push(CommodityLotRecord {
+                    commodity: currency.to_owned(),
+                    datetime: None,
+                    amount: -remaining,
+                    price: None,
+                }),
+            }
+        }
+        Ok(reduced_lots)
+    }
+
+    /// adjust the cost of each held lot of given commodity by the adjustment per unit, only lots whose cost is in the same currency are adjusted.
+    pub(crate) fn adjust_account_lots_cost(&mut self, account_name: &str, currency: &str, adjustment: &Amount) -> ZhangResult<()> {
+        let mut store = self.write();
+        let lots = store.commodity_lots.entry(account_name.to_owned()).or_default();
+        for lot in lots.iter_mut().filter(|lot| lot.commodity.eq(currency) && lot.amount.is_positive()) {
+            if let Some(price) = lot.price.as_mut().filter(|price| price.currency.eq(&adjustment.currency)) {
+                price.number = (&price.number).add(&adjustment.number);
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn has_account_lots_with_cost(&mut self, account_name: &str, currency: &str) -> ZhangResult<bool> {
+        let store = self.read();
+        Ok(store
+            .commodity_lots
+            .get(account_name)
+            .map(|lots| {
+                lots.iter()
+                    .any(|lot| lot.commodity.eq(currency) && lot.price.is_some() && lot.amount.is_positive())
+            })
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn insert_realized_gain(
+        &mut self, trx_id: &Uuid, datetime: DateTime<Tz>, account_name: &str, currency: &str, amount: Amount,
+    ) -> ZhangResult<()> {
+        let mut store = self.write();
+        store.realized_gains.push(RealizedGainDomain {
+            trx_id: *trx_id,
+            datetime,
+            account: account_name.to_owned(),
+            commodity: currency.to_owned(),
+            amount,
+        });
+        Ok(())
+    }
+
+    pub fn account_realized_gains(&mut self, account_name: &str) -> ZhangResult<Vec<RealizedGainDomain>> {
+        let store = self.read();
+        Ok(store.realized_gains.iter().filter(|gain| gain.account.eq(account_name)).cloned().collect_vec())
+    }
+
+    pub(crate) fn insert_account_lot(
+        &mut self, account_name: &str, currency: &str, price: Option<Amount>, datetime: Option<DateTime<Tz>>, amount: &BigDecimal,
+    ) -> ZhangResult<()> {
         let mut store = self.write();
         let lot_records = store.commodity_lots.entry(account_name.to_owned()).or_default();
 
         lot_records.push(CommodityLotRecord {
             commodity: currency.to_owned(),
-            datetime: None,
+            datetime,
             amount: amount.clone(),
             price,
         });
@@ -419,6 +525,19 @@ impl Operations {
         Ok(store.transactions.len() as i64)
     }
 
+    /// the datetime of the earliest and the latest transaction, return `None` if there is no transaction in ledger.
+    /// the transactions generated by balance check and balance pad are excluded.
+    pub fn transaction_datetime_range(&mut self) -> ZhangResult<Option<(DateTime<Tz>, DateTime<Tz>)>> {
+        let store = self.read();
+        Ok(store
+            .transactions
+            .values()
+            .filter(|trx| trx.flag != Flag::BalanceCheck && trx.flag != Flag::BalancePad)
+            .map(|trx| trx.datetime)
+            .minmax()
+            .into_option())
+    }
+
     pub fn transaction_span(&mut self, id: &str) -> ZhangResult<TransactionInfoDomain> {
         let store = self.read();
         Ok(store
@@ -469,6 +588,31 @@ impl Operations {
             .collect_vec())
     }
 
+    /// the latest balance of every account in each commodity, which are aggregated in a single pass of all postings
+    pub fn all_account_balances(&mut self) -> ZhangResult<Vec<AccountBalanceDomain>> {
+        let store = self.read();
+
+        let mut ret: IndexMap<(String, Currency), (NaiveDate, Amount)> = IndexMap::new();
+        for posting in store.postings.iter().sorted_by_key(|posting| posting.trx_datetime) {
+            let date = posting.trx_datetime.naive_local().date();
+            ret.insert(
+                (posting.account.name().to_owned(), posting.after_amount.currency.clone()),
+                (date, posting.after_amount.clone()),
+            );
+        }
+
+        Ok(ret
+            .into_iter()
+            .map(|((account, _), (date, amount))| AccountBalanceDomain {
+                datetime: date.and_time(NaiveTime::default()),
+                account,
+                account_status: AccountStatus::Open,
+                balance_number: amount.number,
+                balance_commodity: amount.currency,
+            })
+            .collect_vec())
+    }
+
     pub fn account_journals(&mut self, account: &str) -> ZhangResult<Vec<AccountJournalDomain>> {
         let store = self.read();
         let account = Account::from_str(account).map_err(|_| ZhangError::InvalidAccount)?;
@@ -498,6 +642,12 @@ impl Operations {
         Ok(ret)
     }
 
+    /// ids of transactions which are transfers between own accounts
+    pub fn transfer_transaction_ids(&self) -> ZhangResult<HashSet<Uuid>> {
+        let store = self.read();
+        Ok(store.transactions.values().filter(|trx| trx.is_transfer()).map(|trx| trx.id).collect())
+    }
+
     pub fn dated_journals(&mut self, from: DateTime<Utc>, to: DateTime<Utc>) -> ZhangResult<Vec<PostingDomain>> {
         let store = self.read();
         Ok(store
@@ -586,6 +736,19 @@ impl Operations {
             .cloned()
             .collect_vec())
     }
+    /// accounts whose name contains the query case-insensitively, ranked by the position of match, then by account name.
+    pub fn search_accounts(&mut self, query: &str, limit: usize) -> ZhangResult<Vec<AccountDomain>> {
+        let store = self.read();
+        let query = query.to_lowercase();
+        Ok(store
+            .accounts
+            .values()
+            .filter_map(|account| account.name.to_lowercase().find(&query).map(|position| (position, account)))
+            .sorted_by(|(a_position, a), (b_position, b)| a_position.cmp(b_position).then_with(|| a.name.cmp(&b.name)))
+            .take(limit)
+            .map(|(_, account)| account.clone())
+            .collect_vec())
+    }
     pub fn all_accounts(&mut self) -> ZhangResult<Vec<String>> {
         let store = self.read();
         Ok(store.accounts.keys().map(|it| it.to_owned()).collect_vec())
@@ -674,6 +837,25 @@ impl Operations {
             })
             .collect_vec())
     }
+
+    /// sum the numeric value of given transaction meta across transactions within `[from, to]`, non-numeric values are skipped.
+    pub fn sum_transaction_meta(&self, key: impl AsRef<str>, from: DateTime<Utc>, to: DateTime<Utc>) -> ZhangResult<BigDecimal> {
+        let store = self.read();
+        let transaction_ids: HashSet<String> = store
+            .transactions
+            .values()
+            .filter(|trx| trx.datetime.ge(&from) && trx.datetime.le(&to))
+            .map(|trx| trx.id.to_string())
+            .collect();
+        Ok(store
+            .metas
+            .iter()
+            .filter(|meta| meta.meta_type.eq(MetaType::TransactionMeta.as_ref()))
+            .filter(|meta| meta.key.eq(key.as_ref()))
+            .filter(|meta| transaction_ids.contains(&meta.type_identifier))
+            .filter_map(|meta| BigDecimal::from_str(meta.value.trim()).ok())
+            .sum())
+    }
 }
 
 // for insert and new operations
@@ -733,7 +915,7 @@ impl Operations {
     }
 
     pub fn insert_commodity(
-        &mut self, name: &String, precision: i32, prefix: Option<String>, suffix: Option<String>, rounding: Option<String>,
+        &mut self, name: &String, precision: i32, display_precision: i32, prefix: Option<String>, suffix: Option<String>, rounding: Option<String>,
     ) -> ZhangResult<()> {
         let mut store = self.write();
         store.commodities.insert(
@@ -741,6 +923,7 @@ impl Operations {
             CommodityDomain {
                 name: name.to_owned(),
                 precision,
+                display_precision,
                 prefix,
                 suffix,
                 rounding,
@@ -816,6 +999,56 @@ impl Operations {
             }))
     }
 
+    /// roll up target month's details of budgets whose related accounts are under the given parent account, the parent itself included.
+    /// amounts are grouped by the commodity of budgets.
+    pub fn budget_rollup_month_detail(&self, parent_account: &str, interval: u32) -> ZhangResult<Option<BudgetRollupDetail>> {
+        let child_prefix = format!("{}:", parent_account);
+        let budget_names = {
+            let store = self.read();
+            store
+                .metas
+                .iter()
+                .filter(|meta| meta.meta_type.eq(MetaType::AccountMeta.as_ref()))
+                .filter(|meta| meta.key.eq("budget"))
+                .filter(|meta| meta.type_identifier.eq(parent_account) || meta.type_identifier.starts_with(&child_prefix))
+                .map(|meta| meta.value.clone())
+                .filter(|name| store.budgets.contains_key(name))
+                .unique()
+                .sorted()
+                .collect_vec()
+        };
+
+        let mut budgets = vec![];
+        let mut amounts: Vec<BudgetRollupAmount> = vec![];
+        for name in budget_names {
+            let Some(detail) = self.budget_month_detail(&name, interval)? else {
+                continue;
+            };
+            budgets.push(name.clone());
+            match amounts.iter_mut().find(|it| it.commodity.eq(&detail.assigned_amount.currency)) {
+                Some(amount) => {
+                    amount.budgets.push(name);
+                    amount.assigned_amount = (&amount.assigned_amount).add(detail.assigned_amount.number);
+                    amount.activity_amount = (&amount.activity_amount).add(detail.activity_amount.number);
+                }
+                None => amounts.push(BudgetRollupAmount {
+                    commodity: detail.assigned_amount.currency.clone(),
+                    budgets: vec![name],
+                    assigned_amount: detail.assigned_amount,
+                    activity_amount: detail.activity_amount,
+                }),
+            }
+        }
+        if budgets.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(BudgetRollupDetail {
+            date: interval,
+            budgets,
+            amounts,
+        }))
+    }
+
     /// add amount to target month's budget
     pub fn budget_add_assigned_amount(&mut self, name: impl Into<String>, date: DateTime<Tz>, event_type: BudgetEventType, amount: Amount) -> ZhangResult<()> {
         let name = name.into();
@@ -892,3 +1125,245 @@ impl Operations {
         Ok(metas.into_iter().filter(|meta| meta.key.eq("budget")).map(|meta| meta.value).collect_vec())
     }
 }
+
+/// Report Related Operations
+impl Operations {
+    fn operating_currency(&mut self) -> ZhangResult<String> {
+        Ok(self
+            .option(KEY_OPERATING_CURRENCY)?
+            .map(|it| it.value)
+            .unwrap_or_else(|| DEFAULT_OPERATING_CURRENCY.to_owned()))
+    }
+
+    /// the balance of given account at the given datetime, converted into operating currency.
+    /// commodity without price into operating currency is reported as error instead of being skipped, which would make the report wrong silently.
+    fn account_calculated_balance(&mut self, account_name: &str, datetime: DateTime<Tz>) -> ZhangResult<BigDecimal> {
+        let operating_currency = self.operating_currency()?;
+        let balances = self
+            .account_target_date_balance(account_name, datetime.with_timezone(&Utc))?
+            .into_iter()
+            .map(|balance| Amount::new(balance.balance_number, balance.balance_commodity))
+            .collect_vec();
+        for balance in balances.iter().filter(|it| it.currency.ne(&operating_currency) && !it.number.is_zero()) {
+            if self.get_price(datetime.naive_local(), &balance.currency, &operating_currency)?.is_none() {
+                return Err(ZhangError::PriceNotFound(balance.currency.clone(), operating_currency));
+            }
+        }
+        Ok(balances.calculate(datetime, self)?.calculated.number)
+    }
+
+    /// generate the trial balance at the given datetime.
+    /// every account's balance is converted into operating currency, positive balance goes to debit column and negative one goes to credit column.
+    pub fn trial_balance(&mut self, datetime: DateTime<Tz>) -> ZhangResult<TrialBalanceDomain> {
+        let operating_currency = self.operating_currency()?;
+
+        let mut items = vec![];
+        for account_name in self.all_accounts()?.into_iter().sorted() {
+            let number = self.account_calculated_balance(&account_name, datetime)?;
+            if number.is_zero() {
+                continue;
+            }
+            let (debit, credit) = if number.is_positive() {
+                (number, BigDecimal::zero())
+            } else {
+                (BigDecimal::zero(), number.abs())
+            };
+            items.push(TrialBalanceItemDomain {
+                account: account_name,
+                debit,
+                credit,
+            });
+        }
+
+        let total_debit = items.iter().map(|it| &it.debit).sum();
+        let total_credit = items.iter().map(|it| &it.credit).sum();
+        Ok(TrialBalanceDomain {
+            datetime: datetime.naive_local(),
+            commodity: operating_currency,
+            items,
+            total_debit,
+            total_credit,
+        })
+    }
+
+    /// generate the balance sheet at the given datetime in operating currency.
+    /// amounts of credit-normal accounts (liabilities and equity) are negated, so that all sections are presented as positive numbers.
+    pub fn balance_sheet(&mut self, datetime: DateTime<Tz>) -> ZhangResult<BalanceSheetDomain> {
+        let operating_currency = self.operating_currency()?;
+
+        let mut assets = vec![];
+        let mut liabilities = vec![];
+        let mut equity = vec![];
+        let mut earnings = BigDecimal::zero();
+        for account_name in self.all_accounts()?.into_iter().sorted() {
+            let account = Account::from_str(&account_name).map_err(|_| ZhangError::InvalidAccount)?;
+            let number = self.account_calculated_balance(&account_name, datetime)?;
+            let section = match account.account_type {
+                AccountType::Assets => &mut assets,
+                AccountType::Liabilities => &mut liabilities,
+                AccountType::Equity => &mut equity,
+                AccountType::Income | AccountType::Expenses => {
+                    earnings.sub_assign(&number);
+                    continue;
+                }
+            };
+            if number.is_zero() {
+                continue;
+            }
+            let amount = if account.account_type.positive_type() { number } else { -number };
+            section.push(StatementItemDomain { account: account_name, amount });
+        }
+
+        let assets = StatementSectionDomain::new(assets);
+        let liabilities = StatementSectionDomain::new(liabilities);
+        let equity = StatementSectionDomain::new(equity);
+        let total_liabilities_and_equity = (&liabilities.total).add(&equity.total).add(&earnings);
+        Ok(BalanceSheetDomain {
+            datetime: datetime.naive_local(),
+            commodity: operating_currency,
+            assets,
+            liabilities,
+            equity,
+            retained_earnings: earnings,
+            total_liabilities_and_equity,
+        })
+    }
+
+    /// generate the income statement of postings within `[from, to]` in operating currency.
+    /// income is presented as positive number, and net income is income minus expenses.
+    pub fn income_statement(&mut self, from: DateTime<Tz>, to: DateTime<Tz>) -> ZhangResult<IncomeStatementDomain> {
+        let operating_currency = self.operating_currency()?;
+
+        let mut account_amounts: BTreeMap<String, (AccountType, Vec<Amount>)> = BTreeMap::new();
+        {
+            let store = self.read();
+            for posting in store
+                .postings
+                .iter()
+                .filter(|posting| matches!(posting.account.account_type, AccountType::Income | AccountType::Expenses))
+                .filter(|posting| posting.trx_datetime.ge(&from) && posting.trx_datetime.le(&to))
+            {
+                account_amounts
+                    .entry(posting.account.name().to_owned())
+                    .or_insert_with(|| (posting.account.account_type, vec![]))
+                    .1
+                    .push(posting.inferred_amount.clone());
+            }
+        }
+
+        let mut income = vec![];
+        let mut expenses = vec![];
+        for (account, (account_type, amounts)) in account_amounts {
+            let number = amounts.calculate(to, self)?.calculated.number;
+            if number.is_zero() {
+                continue;
+            }
+            match account_type {
+                AccountType::Income => income.push(StatementItemDomain { account, amount: -number }),
+                _ => expenses.push(StatementItemDomain { account, amount: number }),
+            }
+        }
+
+        let income = StatementSectionDomain::new(income);
+        let expenses = StatementSectionDomain::new(expenses);
+        let net_income = (&income.total).sub(&expenses.total);
+        Ok(IncomeStatementDomain {
+            from: from.naive_local(),
+            to: to.naive_local(),
+            commodity: operating_currency,
+            income,
+            expenses,
+            net_income,
+        })
+    }
+
+    /// list commodities held by assets or liabilities accounts at `as_of`, whose latest price into operating currency is older than
+    /// the days configured by option `price_staleness_days` at `as_of`, so that the net worth calculated by them may be outdated.
+    /// invalid option value is reported as an error on loading, and the default value is used here.
+    pub fn stale_prices(&mut self, as_of: DateTime<Tz>) -> ZhangResult<Vec<StalePriceDomain>> {
+        let operating_currency = self.operating_currency()?;
+        let staleness_days = match self.option(KEY_PRICE_STALENESS_DAYS)? {
+            Some(option) => option.value.trim().parse::<i64>().unwrap_or(DEFAULT_PRICE_STALENESS_DAYS),
+            None => DEFAULT_PRICE_STALENESS_DAYS,
+        };
+        let as_of_datetime = as_of.naive_local();
+
+        let store = self.read();
+        let mut holdings: HashMap<&str, BigDecimal> = HashMap::new();
+        for posting in store
+            .postings
+            .iter()
+            .filter(|posting| matches!(posting.account.account_type, AccountType::Assets | AccountType::Liabilities))
+            .filter(|posting| posting.trx_datetime.le(&as_of))
+        {
+            let unit = posting.unit.as_ref().unwrap_or(&posting.inferred_amount);
+            holdings.entry(&unit.currency).or_insert_with(BigDecimal::zero).add_assign(&unit.number);
+        }
+        let held_commodities = holdings
+            .into_iter()
+            .filter(|(commodity, number)| !number.is_zero() && operating_currency.ne(commodity))
+            .map(|(commodity, _)| commodity.to_owned())
+            .sorted();
+
+        let mut ret = vec![];
+        for commodity in held_commodities {
+            let latest_price_datetime = store
+                .prices
+                .iter()
+                .filter(|price| price.commodity.eq(&commodity) && price.target_commodity.eq(&operating_currency))
+                .map(|price| price.datetime)
+                .filter(|datetime| datetime.le(&as_of_datetime))
+                .max();
+            let days = latest_price_datetime.map(|datetime| (as_of_datetime.date() - datetime.date()).num_days());
+            if days.map(|days| days > staleness_days).unwrap_or(true) {
+                ret.push(StalePriceDomain {
+                    commodity,
+                    target_commodity: operating_currency.clone(),
+                    latest_price_datetime,
+                    staleness_days: days,
+                });
+            }
+        }
+        Ok(ret)
+    }
+
+    /// the mean monthly expense of given category (the account itself and all its sub-accounts) over the trailing `months` months,
+    /// converted into operating currency.
+    /// only whole calendar months are counted: the month `as_of` belongs to is partial and excluded, so the window ends at the first day of that month.
+    pub fn average_monthly_spend(&mut self, category: &str, months: u32, as_of: DateTime<Tz>) -> ZhangResult<AverageMonthlySpendDomain> {
+        let operating_currency = self.operating_currency()?;
+        let timezone = as_of.timezone();
+
+        let to = as_of.date_naive().with_day(1).expect("first day of month always exists");
+        let from = to.checked_sub_months(Months::new(months)).unwrap_or(NaiveDate::MIN);
+        let to_datetime = local_datetime(&timezone, to.and_time(NaiveTime::default()))?;
+        let from_datetime = local_datetime(&timezone, from.and_time(NaiveTime::default()))?;
+
+        let sub_account_prefix = format!("{}:", category);
+        let amounts = {
+            let store = self.read();
+            store
+                .postings
+                .iter()
+                .filter(|posting| posting.account.name().eq(category) || posting.account.name().starts_with(&sub_account_prefix))
+                .filter(|posting| posting.trx_datetime.ge(&from_datetime) && posting.trx_datetime.lt(&to_datetime))
+                .map(|posting| posting.inferred_amount.clone())
+                .collect_vec()
+        };
+        let total = amounts.calculate(to_datetime, self)?.calculated.number;
+        let average = if months == 0 {
+            BigDecimal::zero()
+        } else {
+            (&total).div(BigDecimal::from(months))
+        };
+        Ok(AverageMonthlySpendDomain {
+            category: category.to_owned(),
+            months,
+            from,
+            to,
+            commodity: operating_currency,
+            total,
+            average,
+        })
+    }
+}
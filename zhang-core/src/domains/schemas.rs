@@ -27,6 +27,8 @@ pub struct AccountDomain {
     pub name: String,
     pub status: AccountStatus,
     pub alias: Option<String>,
+    /// the file where the account is opened
+    pub defined_in: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Copy, Serialize, AsRefStr, EnumString)]
@@ -72,7 +74,10 @@ pub struct MetaDomain {
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct CommodityDomain {
     pub name: String,
+    /// precision used to check whether transaction is balanced
     pub precision: i32,
+    /// precision used to display amounts
+    pub display_precision: i32,
     pub prefix: Option<String>,
     pub suffix: Option<String>,
     pub rounding: Option<String>,
@@ -115,8 +120,98 @@ pub enum ErrorType {
     AccountClosed,
     TransactionDoesNotBalance,
     CommodityDoesNotDefine,
+    DefaultCommodityDoesNotDefine,
+    CurrencySymbolDoesNotDefine,
     TransactionHasMultipleImplicitPosting,
     CloseNonZeroAccount,
+    DocumentPathOutsideBase,
+    InvalidOptionValue,
 
     BudgetDoesNotExist,
 }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrialBalanceItemDomain {
+    pub account: String,
+    pub debit: BigDecimal,
+    pub credit: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrialBalanceDomain {
+    pub datetime: NaiveDateTime,
+    pub commodity: String,
+    pub items: Vec<TrialBalanceItemDomain>,
+    pub total_debit: BigDecimal,
+    pub total_credit: BigDecimal,
+}
+
+impl TrialBalanceDomain {
+    /// a ledger is balanced only if the grand totals of debit and credit are equal
+    pub fn is_balanced(&self) -> bool {
+        self.total_debit.eq(&self.total_credit)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementItemDomain {
+    pub account: String,
+    pub amount: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementSectionDomain {
+    pub items: Vec<StatementItemDomain>,
+    pub total: BigDecimal,
+}
+
+impl StatementSectionDomain {
+    pub fn new(items: Vec<StatementItemDomain>) -> Self {
+        let total = items.iter().map(|it| &it.amount).sum();
+        Self { items, total }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceSheetDomain {
+    pub datetime: NaiveDateTime,
+    pub commodity: String,
+    pub assets: StatementSectionDomain,
+    pub liabilities: StatementSectionDomain,
+    pub equity: StatementSectionDomain,
+    /// accumulated net income of all income and expenses accounts, which is not closed into equity accounts yet
+    pub retained_earnings: BigDecimal,
+    pub total_liabilities_and_equity: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IncomeStatementDomain {
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    pub commodity: String,
+    pub income: StatementSectionDomain,
+    pub expenses: StatementSectionDomain,
+    pub net_income: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StalePriceDomain {
+    pub commodity: String,
+    pub target_commodity: String,
+    /// both are `None` if the commodity has never been priced
+    pub latest_price_datetime: Option<NaiveDateTime>,
+    pub staleness_days: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AverageMonthlySpendDomain {
+    pub category: String,
+    pub months: u32,
+    /// the first day of the earliest month in the window, inclusive
+    pub from: NaiveDate,
+    /// the first day of the month `as_of` belongs to, exclusive
+    pub to: NaiveDate,
+    pub commodity: String,
+    pub total: BigDecimal,
+    pub average: BigDecimal,
+}
@@ -1,6 +1,7 @@
 use std::net::AddrParseError;
 use std::path::{Path, PathBuf};
 
+use chrono::NaiveDate;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -22,6 +23,24 @@ pub enum ZhangError {
     PestError(String),
     #[error("cannot found option given key: {0}")]
     OptionNotFound(String),
+    #[error("cannot found transaction given span: {0}")]
+    TransactionNotFound(String),
+    #[error("transaction at {0} is changed since last loading, reload and try again")]
+    StaleTransactionSpan(String),
+    #[error("transaction at {0} has no line under header")]
+    TransactionWithoutPosting(String),
+    #[error("transaction is not balanced")]
+    UnbalancedTransaction,
+    #[error("transaction cannot be converted: {0}")]
+    InconvertibleTransaction(String),
+    #[error("cannot modify directive dated {0}, the period is closed through {1}")]
+    ClosedPeriod(NaiveDate, NaiveDate),
+    #[error("cannot find price to convert {0} into {1}")]
+    PriceNotFound(String, String),
+    #[error("document path {0} is outside of documents directory")]
+    InvalidDocumentPath(String),
+    #[error("cannot restate operating currency into {0}, no direct price dated before the first posting is found for: {1}")]
+    MissingDirectPrice(String, String),
 
     #[error("invalid content encoding: {0}")]
     ContentEncodingError(#[from] std::string::FromUtf8Error),
@@ -1,21 +1,28 @@
 use std::cmp::Ordering;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::ops::AddAssign;
+use std::path::{Component, Path, PathBuf};
 use std::sync::atomic::AtomicI32;
 use std::sync::{Arc, RwLock};
 
-use bigdecimal::Zero;
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDate;
 use itertools::Itertools;
 use log::{error, info};
-use zhang_ast::{Directive, DirectiveType, Spanned, Transaction};
+use zhang_ast::amount::Amount;
+use zhang_ast::{Account, AccountType, Comment, Currency, Date, Directive, DirectiveType, Flag, Posting, SpanInfo, Spanned, Transaction, ZhangString};
 
+use crate::constants::{KEY_CLOSED_THROUGH, KEY_DOCUMENTS_PATH, KEY_OPERATING_CURRENCY};
 use crate::data_source::DataSource;
+use crate::data_type::text::exporter::ZhangDataTypeExportable;
+use crate::data_type::text::parser::parse as parse_zhang;
 use crate::domains::Operations;
 use crate::error::IoErrorIntoZhangError;
 use crate::options::{BuiltinOption, InMemoryOptions};
 use crate::process::DirectiveProcess;
-use crate::store::Store;
+use crate::store::{Store, TransactionDomain};
 use crate::utils::bigdecimal_ext::BigDecimalExt;
-use crate::ZhangResult;
+use crate::{ZhangError, ZhangResult};
 
 pub struct Ledger {
     pub entry: (PathBuf, String),
@@ -143,10 +150,17 @@ impl Ledger {
     }
 
     pub fn is_transaction_balanced(&self, txn: &Transaction) -> ZhangResult<bool> {
+        Ok(self.transaction_rounding_residual(txn)?.is_some())
+    }
+
+    /// the pre-rounding imbalance of each currency which is absorbed by the tolerance of commodity precision,
+    /// return `None` if the transaction does not balance after rounding.
+    pub fn transaction_rounding_residual(&self, txn: &Transaction) -> ZhangResult<Option<Vec<Amount>>> {
         // 1. get the txn's inventory
         Ok(match txn.get_postings_inventory() {
             Ok(inventory) => {
-                for (currency, amount) in inventory.currencies.iter() {
+                let mut residual = vec![];
+                for (currency, amount) in inventory.currencies.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
                     let mut operations = self.operations();
                     let commodity = operations.commodity(currency)?;
                     let precision = commodity
@@ -159,15 +173,291 @@ impl Ledger {
                         .unwrap_or_else(|| self.options.default_rounding.is_up());
                     let decimal = amount.total.round_with(precision as i64, rounding);
                     if !decimal.is_zero() {
-                        return Ok(false);
+                        return Ok(None);
                     }
+                    residual.push(Amount::new(amount.total.clone(), currency.clone()));
                 }
-                true
+                Some(residual)
             }
-            Err(_) => false,
+            Err(_) => None,
         })
     }
 
+    /// the directive stream after processing, in which implicit postings are resolved.
+    /// pad is kept as the original `balance with pad` directive, since the materialized transaction and balance check cannot be loaded back
+    /// in the same order, the materialized transaction is attached as the comment following it instead.
+    /// include directives are dropped since all included files have been merged into the stream,
+    /// and builtin options not set in source are dropped as well.
+    pub fn processed_directives(&self) -> ZhangResult<Vec<Spanned<Directive>>> {
+        let store = self.store.read().unwrap();
+        let mut ret = vec![];
+        for directive in self.metas.iter().rev().chain(self.directives.iter()) {
+            match &directive.data {
+                Directive::Include(_) => {}
+                Directive::Option(_) if directive.span.eq(&SpanInfo::default()) => {}
+                Directive::Transaction(trx) => {
+                    let mut trx = trx.clone();
+                    let inferred_units = trx.txn_postings().iter().map(|it| it.infer_trade_amount().ok()).collect_vec();
+                    for (posting, inferred_unit) in trx.postings.iter_mut().zip(inferred_units) {
+                        if posting.units.is_none() {
+                            posting.units = inferred_unit;
+                        }
+                    }
+                    ret.push(Spanned::new(Directive::Transaction(trx), directive.span.clone()));
+                }
+                Directive::BalancePad(pad) => {
+                    ret.push(Spanned::new(Directive::BalancePad(pad.clone()), directive.span.clone()));
+                    let pad_trx = store.transactions.values().find(|it| it.flag == Flag::BalancePad && it.span == directive.span);
+                    if let Some(pad_trx) = pad_trx {
+                        let trx = Transaction {
+                            date: pad.date.clone(),
+                            flag: Some(Flag::Okay),
+                            payee: pad_trx.payee.clone().map(ZhangString::quote),
+                            narration: pad_trx.narration.clone().map(ZhangString::quote),
+                            tags: Default::default(),
+                            links: Default::default(),
+                            postings: pad_trx
+                                .postings
+                                .iter()
+                                .map(|posting| Posting {
+                                    flag: None,
+                                    account: posting.account.clone(),
+                                    units: Some(posting.inferred_amount.clone()),
+                                    cost: None,
+                                    cost_date: None,
+                                    price: None,
+                                    comment: None,
+                                    meta: Default::default(),
+                                })
+                                .collect(),
+                            meta: Default::default(),
+                        };
+                        let content = trx.export().lines().map(|line| format!("; {}", line)).join("\n");
+                        ret.push(Spanned::new(Directive::Comment(Comment { content }), directive.span.clone()));
+                    }
+                }
+                other => ret.push(Spanned::new(other.clone(), directive.span.clone())),
+            }
+        }
+        Ok(ret)
+    }
+
+    /// generate the closing transaction which transfers balances of all income and expenses accounts into the equity account as of `period_end`,
+    /// so that all income and expenses accounts are zeroed out. empty vec is returned if there is nothing to close.
+    /// all postings on the day of `period_end` are closed, no matter what time they are at.
+    pub fn generate_closing(&self, period_end: Date, equity_account: &Account) -> Vec<Directive> {
+        let timezone = &self.options.timezone;
+        let period_end_date = period_end.naive_date();
+        let store = self.store.read().unwrap();
+
+        let mut balances: BTreeMap<(String, Currency), (Account, BigDecimal)> = BTreeMap::new();
+        for posting in store
+            .postings
+            .iter()
+            .filter(|posting| matches!(posting.account.account_type, AccountType::Income | AccountType::Expenses))
+            .filter(|posting| posting.trx_datetime.with_timezone(timezone).date_naive().le(&period_end_date))
+        {
+            balances
+                .entry((posting.account.name().to_owned(), posting.inferred_amount.currency.clone()))
+                .or_insert_with(|| (posting.account.clone(), BigDecimal::zero()))
+                .1
+                .add_assign(&posting.inferred_amount.number);
+        }
+
+        let mut equity_amounts: BTreeMap<Currency, BigDecimal> = BTreeMap::new();
+        let mut postings = vec![];
+        for ((_, currency), (account, number)) in balances.into_iter().filter(|(_, (_, number))| !number.is_zero()) {
+            equity_amounts.entry(currency.clone()).or_insert_with(BigDecimal::zero).add_assign(&number);
+            postings.push(Posting {
+                flag: None,
+                account,
+                units: Some(Amount::new(-number, currency)),
+                cost: None,
+                cost_date: None,
+                price: None,
+                comment: None,
+                meta: Default::default(),
+            });
+        }
+        if postings.is_empty() {
+            return vec![];
+        }
+        for (currency, number) in equity_amounts.into_iter().filter(|(_, number)| !number.is_zero()) {
+            postings.push(Posting {
+                flag: None,
+                account: equity_account.clone(),
+                units: Some(Amount::new(number, currency)),
+                cost: None,
+                cost_date: None,
+                price: None,
+                comment: None,
+                meta: Default::default(),
+            });
+        }
+
+        vec![Directive::Transaction(Transaction {
+            date: period_end,
+            flag: Some(Flag::Okay),
+            payee: Some(ZhangString::quote("Closing")),
+            narration: Some(ZhangString::quote(format!("close income and expenses into {}", equity_account.name()))),
+            tags: Default::default(),
+            links: Default::default(),
+            postings,
+            meta: Default::default(),
+        })]
+    }
+
+    fn closed_through(&self) -> ZhangResult<Option<NaiveDate>> {
+        let mut operations = self.operations();
+        let Some(closed_through) = operations.option(KEY_CLOSED_THROUGH)? else {
+            return Ok(None);
+        };
+        NaiveDate::parse_from_str(&closed_through.value, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| ZhangError::InvalidDate)
+    }
+
+    /// reject the directives to be appended if any of them is dated on or before the date of option `closed_through`
+    pub fn check_closed_period(&self, directives: &[Directive]) -> ZhangResult<()> {
+        let Some(closed_through) = self.closed_through()? else {
+            return Ok(());
+        };
+        for datetime in directives.iter().filter_map(|it| it.datetime()) {
+            if datetime.date() <= closed_through {
+                return Err(ZhangError::ClosedPeriod(datetime.date(), closed_through));
+            }
+        }
+        Ok(())
+    }
+
+    /// reject the new content of source file if it adds, removes or changes any directive dated in the closed period
+    pub fn check_closed_period_content(&self, path: &str, old_content: &str, new_content: &str) -> ZhangResult<()> {
+        let Some(closed_through) = self.closed_through()? else {
+            return Ok(());
+        };
+        let closed_directives = |content: &str| -> ZhangResult<Vec<Directive>> {
+            Ok(parse_zhang(content, PathBuf::from(path))
+                .map_err(|it| ZhangError::PestError(it.to_string()))?
+                .into_iter()
+                .map(|it| it.data)
+                .filter(|it| it.datetime().map(|datetime| datetime.date() <= closed_through).unwrap_or(false))
+                .collect_vec())
+        };
+        let old_directives = closed_directives(old_content)?;
+        let new_directives = closed_directives(new_content)?;
+        if let Some(changed) = new_directives
+            .iter()
+            .filter(|it| !old_directives.contains(it))
+            .chain(old_directives.iter().filter(|it| !new_directives.contains(it)))
+            .next()
+        {
+            let date = changed.datetime().map(|it| it.date()).unwrap_or(closed_through);
+            return Err(ZhangError::ClosedPeriod(date, closed_through));
+        }
+        Ok(())
+    }
+
+    /// append directives into source files, directives in closed period are rejected.
+    /// all mutations of ledger should go through it rather than calling data source directly.
+    pub fn append(&self, directives: Vec<Directive>) -> ZhangResult<()> {
+        self.check_closed_period(&directives)?;
+        self.data_source.append(self, directives)
+    }
+
+    pub async fn async_append(&self, directives: Vec<Directive>) -> ZhangResult<()> {
+        self.check_closed_period(&directives)?;
+        self.data_source.async_append(self, directives).await
+    }
+
+    /// overwrite the content of source file, the content is rejected if any directive in closed period is changed.
+    /// file not loaded yet, like a new file to be included, is checked as well, whose old content is taken as empty if it does not exist.
+    pub async fn async_save(&self, path: String, content: &[u8]) -> ZhangResult<()> {
+        if self.closed_through()?.is_some() {
+            let old_content = match self.data_source.async_get(path.clone()).await {
+                Ok(old_content) => String::from_utf8(old_content)?,
+                Err(ZhangError::IoError(e)) | Err(ZhangError::FileError { e, .. }) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e),
+            };
+            let new_content = String::from_utf8(content.to_vec())?;
+            self.check_closed_period_content(&path, &old_content, &new_content)?;
+        }
+        self.data_source.async_save(self, path, content).await
+    }
+
+    /// restate the whole ledger in a new operating currency, the option in store and in-memory options are changed while source files are kept as is.
+    /// since amounts are converted by direct prices only, every currency used by postings must have a direct price into the new currency
+    /// dated no later than its first posting, otherwise the currencies without such price are listed in error.
+    pub fn restate_operating_currency(&mut self, new: &str) -> ZhangResult<()> {
+        let mut operations = self.operations();
+        let missing_currencies = {
+            let store = operations.read();
+            store
+                .postings
+                .iter()
+                .filter(|posting| posting.inferred_amount.currency.as_str().ne(new))
+                .map(|posting| (posting.inferred_amount.currency.clone(), posting.trx_datetime.naive_local()))
+                .into_group_map()
+                .into_iter()
+                .filter(|(currency, posting_datetimes)| {
+                    let first_posting_datetime = posting_datetimes.iter().min();
+                    !store.prices.iter().any(|price| {
+                        price.commodity.eq(currency) && price.target_commodity.eq(new) && first_posting_datetime.map(|it| price.datetime.le(it)).unwrap_or(true)
+                    })
+                })
+                .map(|(currency, _)| currency)
+                .sorted()
+                .join(", ")
+        };
+        if !missing_currencies.is_empty() {
+            return Err(ZhangError::MissingDirectPrice(new.to_owned(), missing_currencies));
+        }
+        operations.insert_or_update_options(KEY_OPERATING_CURRENCY, new)?;
+        new.clone_into(&mut self.options.operating_currency);
+        Ok(())
+    }
+
+    /// the base directory of documents relative to the entry directory, which is configured by option `documents_path`
+    pub fn documents_base(&self) -> ZhangResult<PathBuf> {
+        let mut operations = self.operations();
+        let Some(documents_path) = operations.option(KEY_DOCUMENTS_PATH)? else {
+            return Ok(PathBuf::new());
+        };
+        normalize_relative_path(Path::new(&documents_path.value)).ok_or(ZhangError::InvalidDocumentPath(documents_path.value))
+    }
+
+    /// the path relative to the entry directory, path not under the entry directory is kept as is.
+    /// data sources differ in whether the path of source file is absolute or relative, paths should be compared after it.
+    pub fn relative_path(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.entry.0).unwrap_or(path).to_path_buf()
+    }
+
+    /// find the transaction located at the given span, `start` is the offset of first byte of transaction in the source file.
+    /// the filename is matched exactly after both sides are made relative to the entry directory.
+    pub fn transaction_by_span(&self, filename: &Path, start: usize) -> ZhangResult<Option<TransactionDomain>> {
+        let filename = self.relative_path(filename);
+        let store = self.store.read().unwrap();
+        Ok(store
+            .transactions
+            .values()
+            .find(|it| it.span.start == start && it.span.filename.as_deref().map(|it| self.relative_path(it)).as_ref() == Some(&filename))
+            .cloned())
+    }
+
+    /// resolve the document filename against the documents base, the returned path is relative to the entry directory.
+    /// filename escaping the documents base, either by `..` or by an absolute path outside of it, is rejected.
+    pub fn resolve_document_path(&self, filename: &str) -> ZhangResult<PathBuf> {
+        let base = self.documents_base()?;
+        let path = Path::new(filename);
+        let resolved = if path.is_absolute() {
+            path.strip_prefix(&self.entry.0).ok().and_then(normalize_relative_path)
+        } else {
+            normalize_relative_path(&base.join(path))
+        };
+        resolved
+            .filter(|it| it.starts_with(&base))
+            .ok_or_else(|| ZhangError::InvalidDocumentPath(filename.to_owned()))
+    }
+
     pub fn reload(&mut self) -> ZhangResult<()> {
         let (entry, endpoint) = &mut self.entry;
         let transform_result = self.data_source.load(entry.to_string_lossy().to_string(), endpoint.clone())?;
@@ -203,6 +493,24 @@ impl Ledger {
     }
 }
 
+/// normalize relative path lexically, return `None` if it is absolute or goes above its root by `..`
+fn normalize_relative_path(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::Normal(part) => normalized.push(part),
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalized)
+}
+
 #[cfg(test)]
 mod test {
 
@@ -511,6 +819,8 @@ mod test {
     }
 
     mod account {
+        use std::path::PathBuf;
+
         use indoc::indoc;
 
         use crate::ledger::test::load_from_temp_str;
@@ -526,5 +836,693 @@ mod test {
             assert!(!operations.exist_account("Assets:Bank2")?);
             Ok(())
         }
+
+        #[test]
+        fn should_record_defined_in_relative_to_entry() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                1970-01-01 open Assets:Bank
+            "#});
+
+            let mut operations = ledger.operations();
+            let account = operations.account("Assets:Bank")?.unwrap();
+            assert_eq!(Some(PathBuf::from("example.zhang")), account.defined_in);
+            Ok(())
+        }
+    }
+
+    mod default_commodity {
+        use bigdecimal::BigDecimal;
+        use indoc::indoc;
+        use zhang_ast::Directive;
+
+        use crate::domains::schemas::ErrorType;
+        use crate::ledger::test::load_from_temp_str;
+
+        #[test]
+        fn should_assign_default_commodity_given_bare_number() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                option "default_commodity" "CNY"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Food
+
+                2023-01-01 "KFC" "Crazy Thursday"
+                  Assets:Bank -50
+                  Expenses:Food 50
+                2023-01-02 balance Assets:Bank -50
+            "#});
+
+            let mut operations = ledger.operations();
+            assert!(operations.errors()?.is_empty());
+
+            let balances = operations.single_account_balances("Assets:Bank")?;
+            assert_eq!(1, balances.len());
+            assert_eq!(BigDecimal::from(-50), balances[0].balance_number);
+            assert_eq!("CNY", balances[0].balance_commodity);
+
+            let trx = ledger
+                .directives
+                .iter()
+                .find_map(|it| match &it.data {
+                    Directive::Transaction(trx) => Some(trx),
+                    _ => None,
+                })
+                .unwrap();
+            assert!(trx.postings.iter().all(|posting| posting.units.as_ref().unwrap().currency.eq("CNY")));
+            Ok(())
+        }
+
+        #[test]
+        fn should_raise_error_given_bare_number_without_default_commodity() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Food
+
+                2023-01-01 "KFC" "Crazy Thursday"
+                  Assets:Bank -50
+                  Expenses:Food
+            "#});
+
+            let mut operations = ledger.operations();
+            let errors = operations.errors()?;
+            assert_eq!(1, errors.len());
+            assert_eq!(ErrorType::DefaultCommodityDoesNotDefine, errors[0].error_type);
+            Ok(())
+        }
+    }
+
+    mod inventory_booking {
+        use bigdecimal::BigDecimal;
+        use chrono::NaiveDate;
+        use indoc::indoc;
+
+        use crate::ledger::test::load_from_temp_str;
+
+        #[test]
+        fn should_override_global_booking_method_by_account_meta() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                option "inventory_booking" "LIFO"
+                1970-01-01 commodity AAPL
+                1970-01-01 commodity USD
+                1970-01-01 open Assets:Cash
+                1970-01-01 open Assets:Lifo
+                1970-01-01 open Assets:Fifo
+                  booking_method: "FIFO"
+
+                2023-01-01 "Buy"
+                  Assets:Lifo 10 AAPL {100 USD}
+                  Assets:Fifo 10 AAPL {100 USD}
+                  Assets:Cash -2000 USD
+
+                2023-02-01 "Buy"
+                  Assets:Lifo 10 AAPL {150 USD}
+                  Assets:Fifo 10 AAPL {150 USD}
+                  Assets:Cash -3000 USD
+
+                2023-03-01 "Sell"
+                  Assets:Lifo -10 AAPL @ 200 USD
+                  Assets:Fifo -10 AAPL @ 200 USD
+                  Assets:Cash 4000 USD
+            "#});
+
+            let mut operations = ledger.operations();
+            assert!(operations.errors()?.is_empty());
+
+            let lifo_gains = operations.account_realized_gains("Assets:Lifo")?;
+            assert_eq!(1, lifo_gains.len());
+            assert_eq!(BigDecimal::from(500), lifo_gains[0].amount.number);
+            assert_eq!("USD", lifo_gains[0].amount.currency);
+
+            let fifo_gains = operations.account_realized_gains("Assets:Fifo")?;
+            assert_eq!(1, fifo_gains.len());
+            assert_eq!(BigDecimal::from(1000), fifo_gains[0].amount.number);
+            assert_eq!("USD", fifo_gains[0].amount.currency);
+            Ok(())
+        }
+
+        #[test]
+        fn should_use_cost_date_as_lot_acquisition_date() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                1970-01-01 commodity AAPL
+                1970-01-01 commodity USD
+                1970-01-01 open Assets:Cash
+                1970-01-01 open Assets:Broker
+
+                2023-01-01 "Buy"
+                  Assets:Broker 10 AAPL {100 USD}
+                  Assets:Cash -1000 USD
+
+                2023-02-01 "Transfer in" "bought in 2022, booked later"
+                  Assets:Broker 10 AAPL {150 USD, 2022-01-01}
+                  Assets:Cash -1500 USD
+
+                2023-03-01 "Sell"
+                  Assets:Broker -10 AAPL @ 200 USD
+                  Assets:Cash 2000 USD
+            "#});
+
+            let mut operations = ledger.operations();
+            assert!(operations.errors()?.is_empty());
+
+            let lots = operations.read().commodity_lots.get("Assets:Broker").cloned().unwrap_or_default();
+            let transferred_lot = lots
+                .iter()
+                .find(|lot| lot.price.as_ref().map(|it| it.number.clone()) == Some(BigDecimal::from(150)))
+                .unwrap();
+            assert_eq!(NaiveDate::from_ymd_opt(2022, 1, 1), transferred_lot.datetime.map(|it| it.date_naive()));
+
+            // the lot acquired in 2022 is the first one to be sold in FIFO
+            let gains = operations.account_realized_gains("Assets:Broker")?;
+            assert_eq!(1, gains.len());
+            assert_eq!(BigDecimal::from(500), gains[0].amount.number);
+            Ok(())
+        }
+
+        #[test]
+        fn should_reflect_cost_adjustment_in_realized_gain() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                1970-01-01 commodity AAPL
+                1970-01-01 commodity USD
+                1970-01-01 open Assets:Cash
+                1970-01-01 open Assets:Broker
+
+                2023-01-01 "Buy"
+                  Assets:Broker 10 AAPL {100 USD}
+                  Assets:Cash -1000 USD
+
+                2023-02-01 "Wash sale adjustment"
+                  Assets:Broker 0 AAPL {+5 USD}
+
+                2023-03-01 "Sell"
+                  Assets:Broker -10 AAPL @ 200 USD
+                  Assets:Cash 2000 USD
+            "#});
+
+            let mut operations = ledger.operations();
+            assert!(operations.errors()?.is_empty());
+
+            let gains = operations.account_realized_gains("Assets:Broker")?;
+            assert_eq!(1, gains.len());
+            assert_eq!(BigDecimal::from(950), gains[0].amount.number);
+            Ok(())
+        }
+    }
+
+    mod processed_directives {
+        use bigdecimal::BigDecimal;
+        use indoc::indoc;
+        use itertools::Itertools;
+
+        use crate::data_type::text::ZhangDataType;
+        use crate::data_type::DataType;
+        use crate::ledger::test::load_from_temp_str;
+        use crate::ledger::Ledger;
+        use crate::ZhangResult;
+
+        #[test]
+        fn should_keep_pad_and_attach_materialized_transaction_as_comment() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Equity:Open
+
+                2023-01-01 balance Assets:Bank 100 CNY with pad Equity:Open
+            "#});
+
+            let data_type = ZhangDataType {};
+            let output = ledger.processed_directives()?.into_iter().map(|it| data_type.export(it)).join("\n\n");
+
+            assert!(output.contains("2023-01-01 balance Assets:Bank 100 CNY with pad Equity:Open"));
+            assert!(output.contains(indoc! {r#"
+                ; 2023-01-01 * "Balance Pad" "pad Assets:Bank to Equity:Open"
+                ;   Assets:Bank 100 CNY
+                ;   Equity:Open -100 CNY"#}));
+            Ok(())
+        }
+
+        #[test]
+        fn should_get_same_balances_given_output_loaded_back() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                option "operating_currency" "CNY"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Food
+                1970-01-01 open Equity:Open
+
+                2023-01-01 "KFC" "Crazy Thursday"
+                  Assets:Bank -10 CNY
+                  Expenses:Food
+
+                2023-01-01 balance Assets:Bank 100 CNY with pad Equity:Open
+            "#});
+
+            let data_type = ZhangDataType {};
+            let output = ledger.processed_directives()?.into_iter().map(|it| data_type.export(it)).join("\n\n");
+            let reloaded = load_from_temp_str(&output);
+
+            let balances = |ledger: &Ledger| -> ZhangResult<Vec<(String, String, BigDecimal)>> {
+                Ok(ledger
+                    .operations()
+                    .all_account_balances()?
+                    .into_iter()
+                    .map(|it| (it.account, it.balance_commodity, it.balance_number))
+                    .sorted()
+                    .collect_vec())
+            };
+            assert!(ledger.operations().errors()?.is_empty());
+            assert!(reloaded.operations().errors()?.is_empty());
+            assert_eq!(balances(&ledger)?, balances(&reloaded)?);
+            assert_eq!(
+                Some(BigDecimal::from(90)),
+                balances(&reloaded)?
+                    .into_iter()
+                    .find(|(account, _, _)| account.eq("Assets:Bank"))
+                    .map(|(_, _, number)| number)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn should_skip_builtin_options_not_in_source() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                option "operating_currency" "USD"
+                1970-01-01 open Assets:Bank
+            "#});
+
+            let data_type = ZhangDataType {};
+            let output = ledger.processed_directives()?.into_iter().map(|it| data_type.export(it)).join("\n\n");
+
+            assert!(output.contains(r#"option "operating_currency" "USD""#));
+            assert!(!output.contains(r#"option "timezone""#));
+            assert!(!output.contains(r#"option "inventory_booking""#));
+            Ok(())
+        }
+
+        #[test]
+        fn should_resolve_implicit_posting() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Food
+
+                2023-01-01 "KFC" "Crazy Thursday"
+                  Assets:Bank -50 CNY
+                  Expenses:Food
+            "#});
+
+            let data_type = ZhangDataType {};
+            let output = ledger.processed_directives()?.into_iter().map(|it| data_type.export(it)).join("\n\n");
+
+            assert!(output.contains("  Expenses:Food 50 CNY"));
+            Ok(())
+        }
+    }
+
+    mod currency_symbol {
+        use std::str::FromStr;
+
+        use bigdecimal::BigDecimal;
+        use indoc::indoc;
+
+        use crate::domains::schemas::ErrorType;
+        use crate::ledger::test::load_from_temp_str;
+
+        #[test]
+        fn should_map_currency_symbol_to_commodity() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                option "currency_symbol" "$:USD,¥:CNY"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Assets:Cash
+                1970-01-01 open Expenses:Food
+
+                2023-01-01 "KFC" "Crazy Thursday"
+                  Assets:Bank -$1,234.56
+                  Assets:Cash ¥100
+                  Expenses:Food $1,234.56
+                  Expenses:Food -¥100
+            "#});
+
+            let mut operations = ledger.operations();
+            assert!(operations.errors()?.is_empty());
+
+            let bank = operations.single_account_balances("Assets:Bank")?;
+            assert_eq!(1, bank.len());
+            assert_eq!(BigDecimal::from_str("-1234.56")?, bank[0].balance_number);
+            assert_eq!("USD", bank[0].balance_commodity);
+
+            let cash = operations.single_account_balances("Assets:Cash")?;
+            assert_eq!(BigDecimal::from(100), cash[0].balance_number);
+            assert_eq!("CNY", cash[0].balance_commodity);
+            Ok(())
+        }
+
+        #[test]
+        fn should_raise_error_given_unmapped_currency_symbol() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                option "currency_symbol" "$:USD"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Food
+
+                2023-01-01 "KFC" "Crazy Thursday"
+                  Assets:Bank -€10
+                  Expenses:Food
+            "#});
+
+            let mut operations = ledger.operations();
+            let errors = operations.errors()?;
+            assert_eq!(1, errors.len());
+            assert_eq!(ErrorType::CurrencySymbolDoesNotDefine, errors[0].error_type);
+            Ok(())
+        }
+    }
+
+    mod closing {
+        use std::str::FromStr;
+
+        use bigdecimal::{BigDecimal, Zero};
+        use chrono::NaiveDate;
+        use indoc::indoc;
+        use itertools::Itertools;
+        use zhang_ast::{Account, Date, Spanned};
+
+        use crate::data_type::text::ZhangDataType;
+        use crate::data_type::DataType;
+        use crate::ledger::test::{fake_span_info, load_from_temp_str};
+
+        const CONTENT: &str = indoc! {r#"
+            1970-01-01 open Assets:Bank
+            1970-01-01 open Income:Salary
+            1970-01-01 open Expenses:Food
+            1970-01-01 open Equity:Earnings
+
+            2023-01-10 "Company" "Salary"
+              Assets:Bank 5000 CNY
+              Income:Salary
+
+            2023-01-20 "KFC" "Crazy Thursday"
+              Assets:Bank -50 CNY
+              Expenses:Food
+
+            2023-02-10 "KFC" "Crazy Thursday"
+              Assets:Bank -30 CNY
+              Expenses:Food
+        "#};
+
+        #[test]
+        fn should_zero_income_and_expenses_after_closing() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(CONTENT);
+            let period_end = Date::Date(NaiveDate::from_ymd_opt(2023, 1, 31).unwrap());
+            let closing = ledger.generate_closing(period_end, &Account::from_str("Equity:Earnings").unwrap());
+            assert_eq!(1, closing.len());
+
+            let data_type = ZhangDataType {};
+            let closing_content = closing.into_iter().map(|it| data_type.export(Spanned::new(it, fake_span_info()))).join("\n");
+            let ledger = load_from_temp_str(&format!("{}\n{}\n", CONTENT, closing_content));
+
+            let mut operations = ledger.operations();
+            assert!(operations.errors()?.is_empty());
+
+            let income = operations.account_target_date_balance(
+                "Income:Salary",
+                NaiveDate::from_ymd_opt(2023, 1, 31).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc(),
+            )?;
+            assert!(income.iter().all(|it| it.balance_number.is_zero()));
+            let expenses = operations.account_target_date_balance(
+                "Expenses:Food",
+                NaiveDate::from_ymd_opt(2023, 1, 31).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc(),
+            )?;
+            assert!(expenses.iter().all(|it| it.balance_number.is_zero()));
+
+            let earnings = operations.single_account_balances("Equity:Earnings")?;
+            assert_eq!(BigDecimal::from(-4950), earnings[0].balance_number);
+
+            // expenses after the period end are not closed
+            let expenses = operations.single_account_balances("Expenses:Food")?;
+            assert_eq!(BigDecimal::from(30), expenses[0].balance_number);
+            Ok(())
+        }
+
+        #[test]
+        fn should_close_timed_posting_on_period_end_day() -> Result<(), Box<dyn std::error::Error>> {
+            let content = format!(
+                "{}\n{}",
+                CONTENT,
+                indoc! {r#"
+                    2023-01-31 18:00:00 "Hotpot" "Dinner"
+                      Assets:Bank -200 CNY
+                      Expenses:Food
+                "#}
+            );
+            let ledger = load_from_temp_str(&content);
+            let period_end = Date::Date(NaiveDate::from_ymd_opt(2023, 1, 31).unwrap());
+            let closing = ledger.generate_closing(period_end, &Account::from_str("Equity:Earnings").unwrap());
+
+            let data_type = ZhangDataType {};
+            let closing_content = closing.into_iter().map(|it| data_type.export(Spanned::new(it, fake_span_info()))).join("\n");
+            assert!(closing_content.contains("  Expenses:Food -250 CNY"));
+
+            let ledger = load_from_temp_str(&format!("{}\n{}\n", content, closing_content));
+            let mut operations = ledger.operations();
+            assert!(operations.errors()?.is_empty());
+            let earnings = operations.single_account_balances("Equity:Earnings")?;
+            assert_eq!(BigDecimal::from(-4750), earnings[0].balance_number);
+            Ok(())
+        }
+
+        #[test]
+        fn should_generate_nothing_given_no_income_and_expenses() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(CONTENT);
+            let period_end = Date::Date(NaiveDate::from_ymd_opt(2022, 12, 31).unwrap());
+            assert!(ledger.generate_closing(period_end, &Account::from_str("Equity:Earnings").unwrap()).is_empty());
+            Ok(())
+        }
+    }
+
+    mod documents_path {
+        use indoc::indoc;
+
+        use crate::domains::schemas::ErrorType;
+        use crate::ledger::test::load_from_temp_str;
+
+        #[test]
+        fn should_resolve_relative_document_under_documents_path() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                option "documents_path" "documents/"
+                1970-01-01 open Assets:Bank
+                2023-01-01 document Assets:Bank "receipts/./statement.pdf"
+            "#});
+
+            let operations = ledger.operations();
+            let store = operations.read();
+            assert_eq!(1, store.documents.len());
+            assert_eq!("documents/receipts/statement.pdf", store.documents[0].path);
+            assert_eq!(Some("statement.pdf".to_owned()), store.documents[0].filename);
+            Ok(())
+        }
+
+        #[test]
+        fn should_reject_document_escaping_documents_path() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                option "documents_path" "documents/"
+                1970-01-01 open Assets:Bank
+                2023-01-01 document Assets:Bank "../main.zhang"
+                2023-01-02 document Assets:Bank "/etc/passwd"
+            "#});
+
+            let mut operations = ledger.operations();
+            assert!(operations.read().documents.is_empty());
+            let errors = operations.errors()?;
+            assert_eq!(2, errors.len());
+            assert!(errors.iter().all(|it| it.error_type == ErrorType::DocumentPathOutsideBase));
+            Ok(())
+        }
+    }
+
+    mod transaction_by_span {
+        use std::path::PathBuf;
+        use std::sync::Arc;
+
+        use indoc::indoc;
+        use tempfile::tempdir;
+
+        use crate::data_source::LocalFileSystemDataSource;
+        use crate::data_type::text::ZhangDataType;
+        use crate::ledger::Ledger;
+
+        #[test]
+        fn should_not_match_file_with_same_name_in_sub_folder() -> Result<(), Box<dyn std::error::Error>> {
+            let temp_dir = tempdir()?.into_path();
+            std::fs::create_dir(temp_dir.join("sub"))?;
+            // both transactions start at the same offset of files with the same name
+            std::fs::write(
+                temp_dir.join("main.zhang"),
+                indoc! {r#"
+                    2023-01-01 "KFC" "Main"
+                      Assets:Bank -50 CNY
+                      Expenses:Food
+
+                    include "sub/main.zhang"
+                "#},
+            )?;
+            std::fs::write(
+                temp_dir.join("sub/main.zhang"),
+                indoc! {r#"
+                    2023-01-02 "KFC" "Sub!"
+                      Assets:Bank -50 CNY
+                      Expenses:Food
+
+                    1970-01-01 open Assets:Bank
+                    1970-01-01 open Expenses:Food
+                "#},
+            )?;
+            let data_source = Arc::new(LocalFileSystemDataSource::new(ZhangDataType {}));
+            let ledger = Ledger::load_with_data_source(temp_dir, "main.zhang".to_owned(), data_source)?;
+
+            let main = ledger.transaction_by_span(&PathBuf::from("main.zhang"), 0)?.unwrap();
+            assert_eq!(Some("Main".to_owned()), main.narration);
+            let sub = ledger.transaction_by_span(&PathBuf::from("sub/main.zhang"), 0)?.unwrap();
+            assert_eq!(Some("Sub!".to_owned()), sub.narration);
+            let absolute = ledger.entry.0.join("main.zhang");
+            assert_eq!(Some("Main".to_owned()), ledger.transaction_by_span(&absolute, 0)?.unwrap().narration);
+            assert!(ledger.transaction_by_span(&PathBuf::from("other/main.zhang"), 0)?.is_none());
+            Ok(())
+        }
+    }
+
+    mod restate_operating_currency {
+        use indoc::indoc;
+
+        use crate::error::ZhangError;
+        use crate::ledger::test::load_from_temp_str;
+
+        const CONTENT: &str = indoc! {r#"
+            option "operating_currency" "CNY"
+            1970-01-01 open Assets:Bank
+            1970-01-01 open Assets:Broker
+            1970-01-01 open Equity:Open
+
+            2023-01-01 price USD 7 CNY
+            2023-01-01 price CNY 0.13 EUR
+            2023-01-01 price USD 0.9 EUR
+
+            2023-01-02 "Opening"
+              Assets:Bank 1000 CNY
+              Assets:Broker 100 USD
+              Assets:Broker 10 AAPL
+              Equity:Open -1700 CNY
+        "#};
+
+        #[test]
+        fn should_error_given_commodity_without_price_to_new_currency() -> Result<(), Box<dyn std::error::Error>> {
+            let mut ledger = load_from_temp_str(CONTENT);
+
+            let result = ledger.restate_operating_currency("EUR");
+            assert!(matches!(result, Err(ZhangError::MissingDirectPrice(currency, missing)) if currency == "EUR" && missing == "AAPL"));
+            assert_eq!("CNY", ledger.operations().option("operating_currency")?.unwrap().value);
+            assert_eq!("CNY", ledger.options.operating_currency);
+            Ok(())
+        }
+
+        #[test]
+        fn should_error_given_price_dated_after_first_posting() -> Result<(), Box<dyn std::error::Error>> {
+            let mut ledger = load_from_temp_str(&format!("{}\n2023-06-01 price AAPL 150 EUR\n", CONTENT));
+
+            let result = ledger.restate_operating_currency("EUR");
+            assert!(matches!(result, Err(ZhangError::MissingDirectPrice(_, missing)) if missing == "AAPL"));
+            Ok(())
+        }
+
+        #[test]
+        fn should_set_operating_currency_given_all_commodities_have_price() -> Result<(), Box<dyn std::error::Error>> {
+            let mut ledger = load_from_temp_str(&format!("{}\n2023-01-01 price AAPL 150 EUR\n", CONTENT));
+
+            ledger.restate_operating_currency("EUR")?;
+            assert_eq!("EUR", ledger.operations().option("operating_currency")?.unwrap().value);
+            assert_eq!("EUR", ledger.options.operating_currency);
+            Ok(())
+        }
+    }
+
+    mod stale_prices {
+        use chrono::{DateTime, Duration, NaiveDate, NaiveTime};
+        use chrono_tz::Tz;
+        use indoc::{formatdoc, indoc};
+
+        use crate::domains::schemas::ErrorType;
+        use crate::ledger::test::load_from_temp_str;
+        use crate::ledger::Ledger;
+        use crate::utils::local_datetime;
+        use crate::ZhangResult;
+
+        fn as_of_date() -> NaiveDate {
+            NaiveDate::from_ymd_opt(2023, 6, 15).unwrap()
+        }
+
+        fn as_of(ledger: &Ledger) -> ZhangResult<DateTime<Tz>> {
+            local_datetime(&ledger.options.timezone, as_of_date().and_time(NaiveTime::default()))
+        }
+
+        fn load_with_price_age(price_age_days: i64) -> Ledger {
+            let as_of_date = as_of_date();
+            load_from_temp_str(&formatdoc! {r#"
+                option "operating_currency" "CNY"
+                option "price_staleness_days" "30"
+                1970-01-01 commodity AAPL
+                1970-01-01 open Assets:Broker
+                1970-01-01 open Equity:Open
+
+                {price_date} price AAPL 1000 CNY
+
+                {trx_date} "Opening"
+                  Assets:Broker 10 AAPL {{900 CNY}}
+                  Equity:Open -9000 CNY
+            "#, price_date = as_of_date - Duration::days(price_age_days), trx_date = as_of_date - Duration::days(90)})
+        }
+
+        #[test]
+        fn should_warn_given_latest_price_older_than_staleness_days() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_with_price_age(60);
+            let mut operations = ledger.operations();
+            let as_of = as_of(&ledger)?;
+
+            let stale_prices = operations.stale_prices(as_of)?;
+            assert_eq!(1, stale_prices.len());
+            assert_eq!("AAPL", stale_prices[0].commodity);
+            assert_eq!("CNY", stale_prices[0].target_commodity);
+            assert_eq!(Some(60), stale_prices[0].staleness_days);
+            Ok(())
+        }
+
+        #[test]
+        fn should_not_warn_given_fresh_price() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_with_price_age(1);
+            let mut operations = ledger.operations();
+            let as_of = as_of(&ledger)?;
+
+            assert!(operations.stale_prices(as_of)?.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn should_not_warn_given_commodity_not_held_at_as_of() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_with_price_age(60);
+            let mut operations = ledger.operations();
+            // the commodity is bought 90 days before
+            let as_of = local_datetime(&ledger.options.timezone, (as_of_date() - Duration::days(100)).and_time(NaiveTime::default()))?;
+
+            assert!(operations.stale_prices(as_of)?.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn should_report_error_given_invalid_staleness_days() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_temp_str(indoc! {r#"
+                option "operating_currency" "CNY"
+                option "price_staleness_days" "30d"
+            "#});
+            let mut operations = ledger.operations();
+
+            let errors = operations.errors()?;
+            assert_eq!(1, errors.len());
+            assert_eq!(ErrorType::InvalidOptionValue, errors[0].error_type);
+            assert_eq!(Some(&"30d".to_owned()), errors[0].metas.get("value"));
+            Ok(())
+        }
     }
 }
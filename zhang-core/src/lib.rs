@@ -1,5 +1,6 @@
 pub use error::ZhangError;
 
+pub mod cache;
 pub mod constants;
 pub mod data_source;
 pub mod data_type;
@@ -274,6 +275,7 @@ mod test {
     mod commodity {
         use indoc::indoc;
 
+        use crate::domains::schemas::ErrorType;
         use crate::test::load_from_text;
 
         #[test]
@@ -369,6 +371,45 @@ mod test {
             assert_eq!(None, commodity.suffix);
             Ok(())
         }
+
+        #[test]
+        fn should_use_same_precision_for_display_and_balance_given_only_one_set() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 commodity CNY
+                  display_precision: "3"
+            "#});
+
+            let mut operations = ledger.operations();
+            let commodity = operations.commodity("CNY")?.unwrap();
+            assert_eq!(3, commodity.precision);
+            assert_eq!(3, commodity.display_precision);
+            Ok(())
+        }
+
+        #[test]
+        fn should_display_and_balance_in_different_precision() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 commodity BTC
+                  precision: "8"
+                  display_precision: "2"
+                1970-01-01 open Assets:Wallet
+                1970-01-01 open Assets:Exchange
+
+                2023-01-01 "Withdraw"
+                  Assets:Wallet 0.12345678 BTC
+                  Assets:Exchange -0.12345677 BTC
+            "#});
+
+            let mut operations = ledger.operations();
+            let commodity = operations.commodity("BTC")?.unwrap();
+            assert_eq!(8, commodity.precision);
+            assert_eq!(2, commodity.display_precision);
+
+            let errors = operations.errors()?;
+            assert_eq!(1, errors.len());
+            assert_eq!(ErrorType::TransactionDoesNotBalance, errors[0].error_type);
+            Ok(())
+        }
     }
     mod error {
         use indoc::indoc;
@@ -473,6 +514,82 @@ mod test {
         }
     }
 
+    mod trial_balance {
+        use bigdecimal::{BigDecimal, Zero};
+        use chrono::NaiveDate;
+        use indoc::indoc;
+
+        use crate::test::load_from_text;
+        use crate::utils::local_datetime;
+        use crate::ZhangError;
+
+        #[test]
+        fn should_have_equal_debit_and_credit_given_balanced_ledger() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(indoc! {r#"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Expenses:Food
+                1970-01-01 open Income:Salary
+                1970-01-01 open Equity:Opening
+
+                2023-01-01 balance Assets:Bank 1000 CNY with pad Equity:Opening
+
+                2023-01-02 "Company" "Salary"
+                  Assets:Bank 500 CNY
+                  Income:Salary
+
+                2023-01-03 "KFC" "Crazy Thursday"
+                  Assets:Bank -50 CNY
+                  Expenses:Food
+            "#});
+
+            let mut operations = ledger.operations();
+            let datetime = local_datetime(
+                &ledger.options.timezone,
+                NaiveDate::from_ymd_opt(2023, 12, 31).unwrap().and_hms_opt(23, 59, 59).unwrap(),
+            )?;
+            let trial_balance = operations.trial_balance(datetime)?;
+
+            assert_eq!(4, trial_balance.items.len());
+            assert_eq!(BigDecimal::from(1500), trial_balance.total_debit);
+            assert_eq!(BigDecimal::from(1500), trial_balance.total_credit);
+            assert!(trial_balance.is_balanced());
+
+            let bank = trial_balance.items.iter().find(|it| it.account.eq("Assets:Bank")).unwrap();
+            assert_eq!(BigDecimal::from(1450), bank.debit);
+            assert_eq!(BigDecimal::zero(), bank.credit);
+            let salary = trial_balance.items.iter().find(|it| it.account.eq("Income:Salary")).unwrap();
+            assert_eq!(BigDecimal::zero(), salary.debit);
+            assert_eq!(BigDecimal::from(500), salary.credit);
+            Ok(())
+        }
+
+        #[test]
+        fn should_error_given_commodity_without_price() -> Result<(), Box<dyn std::error::Error>> {
+            let ledger = load_from_text(indoc! {r#"
+                option "operating_currency" "CNY"
+                1970-01-01 open Assets:Bank
+                1970-01-01 open Assets:Cash
+                1970-01-01 open Equity:Opening
+
+                2023-01-01 "Opening"
+                  Assets:Bank 1000 CNY
+                  Assets:Cash 100 USD
+                  Equity:Opening -1000 CNY
+                  Equity:Opening -100 USD
+            "#});
+
+            let mut operations = ledger.operations();
+            let datetime = local_datetime(
+                &ledger.options.timezone,
+                NaiveDate::from_ymd_opt(2023, 12, 31).unwrap().and_hms_opt(23, 59, 59).unwrap(),
+            )?;
+            let result = operations.trial_balance(datetime);
+
+            assert!(matches!(result, Err(ZhangError::PriceNotFound(currency, target)) if currency == "USD" && target == "CNY"));
+            Ok(())
+        }
+    }
+
     mod transaction {
         use indoc::indoc;
 
@@ -4,10 +4,11 @@ use chrono_tz::Tz;
 use itertools::Itertools;
 use log::{error, info, warn};
 use strum::{AsRefStr, EnumIter, EnumString, IntoEnumIterator};
-use zhang_ast::{Directive, Options, Rounding, SpanInfo, Spanned, ZhangString};
+use zhang_ast::{BookingMethod, Directive, Options, Rounding, SpanInfo, Spanned, ZhangString};
 
 use crate::constants::{
-    DEFAULT_BALANCE_TOLERANCE_PRECISION_PLAIN, DEFAULT_COMMODITY_PRECISION_PLAIN, DEFAULT_OPERATING_CURRENCY, DEFAULT_ROUNDING_PLAIN, DEFAULT_TIMEZONE,
+    DEFAULT_BALANCE_TOLERANCE_PRECISION_PLAIN, DEFAULT_BOOKING_METHOD, DEFAULT_BOOKING_METHOD_PLAIN, DEFAULT_COMMODITY_PRECISION_PLAIN,
+    DEFAULT_OPERATING_CURRENCY, DEFAULT_ROUNDING_PLAIN, DEFAULT_TIMEZONE,
 };
 use crate::domains::Operations;
 use crate::ZhangResult;
@@ -18,6 +19,7 @@ pub struct InMemoryOptions {
     pub default_rounding: Rounding,
     pub default_balance_tolerance_precision: i32,
     pub timezone: Tz,
+    pub default_booking_method: BookingMethod,
 }
 
 #[derive(Debug, AsRefStr, EnumIter, EnumString)]
@@ -29,6 +31,7 @@ pub enum BuiltinOption {
     DefaultBalanceTolerancePrecision,
     DefaultCommodityPrecision,
     Timezone,
+    InventoryBooking,
 }
 
 impl BuiltinOption {
@@ -38,6 +41,7 @@ impl BuiltinOption {
             BuiltinOption::DefaultRounding => DEFAULT_ROUNDING_PLAIN.to_owned(),
             BuiltinOption::DefaultBalanceTolerancePrecision => DEFAULT_BALANCE_TOLERANCE_PRECISION_PLAIN.to_owned(),
             BuiltinOption::DefaultCommodityPrecision => DEFAULT_COMMODITY_PRECISION_PLAIN.to_owned(),
+            BuiltinOption::InventoryBooking => DEFAULT_BOOKING_METHOD_PLAIN.to_owned(),
             BuiltinOption::Timezone => match iana_time_zone::get_timezone() {
                 Ok(timezone) => {
                     info!("detect system timezone is {}", timezone);
@@ -80,7 +84,7 @@ impl InMemoryOptions {
                     let suffix: Option<String> = None;
                     let rounding = Some(self.default_rounding);
 
-                    operation.insert_commodity(&value, precision, prefix, suffix, rounding.map(|it| it.to_string()))?;
+                    operation.insert_commodity(&value, precision, precision, prefix, suffix, rounding.map(|it| it.to_string()))?;
 
                     value.clone_into(&mut self.operating_currency);
                 }
@@ -102,6 +106,15 @@ impl InMemoryOptions {
                         return Ok(BuiltinOption::Timezone.default_value());
                     }
                 },
+                BuiltinOption::InventoryBooking => match BookingMethod::from_str(&value) {
+                    Ok(booking_method) => {
+                        self.default_booking_method = booking_method;
+                    }
+                    Err(_) => {
+                        error!("inventory booking value '{value}' is not a supported booking method, fallback to use FIFO");
+                        return Ok(BuiltinOption::InventoryBooking.default_value());
+                    }
+                },
             }
         }
         Ok(value)
@@ -115,6 +128,7 @@ impl Default for InMemoryOptions {
             default_rounding: Rounding::RoundDown,
             default_balance_tolerance_precision: 2,
             timezone: BuiltinOption::Timezone.default_value().parse().unwrap(),
+            default_booking_method: DEFAULT_BOOKING_METHOD,
         }
     }
 }
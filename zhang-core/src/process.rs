@@ -4,21 +4,25 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::atomic::Ordering;
 
-use bigdecimal::{BigDecimal, Zero};
+use bigdecimal::{BigDecimal, Signed, Zero};
+use chrono::DateTime;
+use chrono_tz::Tz;
 use itertools::Itertools;
 use uuid::Uuid;
 use zhang_ast::amount::Amount;
 use zhang_ast::utils::inventory::LotInfo;
 use zhang_ast::*;
 
-use crate::constants::{DEFAULT_COMMODITY_PRECISION, KEY_DEFAULT_COMMODITY_PRECISION, KEY_DEFAULT_ROUNDING};
+use crate::constants::{
+    DEFAULT_COMMODITY_PRECISION, KEY_CURRENCY_SYMBOL, KEY_DEFAULT_COMMODITY, KEY_DEFAULT_COMMODITY_PRECISION, KEY_DEFAULT_ROUNDING, KEY_PRICE_STALENESS_DAYS,
+};
 use crate::domains::schemas::{AccountStatus, ErrorType, MetaType};
 use crate::domains::{AccountAmount, Operations};
 use crate::ledger::Ledger;
 use crate::store::{BudgetEventType, DocumentType};
 use crate::utils::hashmap::HashMapOfExt;
 use crate::utils::id::FromSpan;
-use crate::ZhangResult;
+use crate::{ZhangError, ZhangResult};
 
 pub(crate) trait DirectiveProcess {
     fn handler(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
@@ -60,9 +64,75 @@ fn check_commodity_define(commodity_name: &str, ledger: &mut Ledger, span: &Span
     Ok(())
 }
 
+/// booking method is resolved in the order of account's meta `booking_method`, option `inventory_booking` and FIFO
+fn account_booking_method(account_name: &str, ledger: &Ledger) -> ZhangResult<BookingMethod> {
+    let operations = ledger.operations();
+    let booking_method = operations
+        .metas(MetaType::AccountMeta, account_name)?
+        .into_iter()
+        .find(|meta| meta.key.eq("booking_method"))
+        .and_then(|meta| BookingMethod::from_str(&meta.value).ok());
+    Ok(booking_method.unwrap_or(ledger.options.default_booking_method))
+}
+
+/// resolve document filename against option `documents_path`, record an error and return `None` if it escapes the documents directory
+fn resolve_document_path(filename: &str, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<Option<PathBuf>> {
+    match ledger.resolve_document_path(filename) {
+        Ok(path) => Ok(Some(path)),
+        Err(ZhangError::InvalidDocumentPath(_)) => {
+            let mut operations = ledger.operations();
+            operations.new_error(ErrorType::DocumentPathOutsideBase, span, HashMap::of("filename", filename.to_owned()))?;
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// the file where directive is defined, which is relative to the ledger root no matter the data source gives absolute path or not
+fn defined_in(ledger: &Ledger, span: &SpanInfo) -> Option<PathBuf> {
+    span.filename.as_deref().map(|filename| ledger.relative_path(filename))
+}
+
+/// amount written as bare number is parsed with empty commodity, which is assigned by option `default_commodity`.
+/// amount led by currency symbol like `$10` is parsed with the symbol as commodity, which is mapped by option `currency_symbol`, e.g. `$:USD,¥:CNY`.
+fn resolve_commodity(amount: &mut Amount, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
+    let mut operations = ledger.operations();
+    if amount.currency.is_empty() {
+        match operations.option(KEY_DEFAULT_COMMODITY)? {
+            Some(default_commodity) => amount.currency = default_commodity.value,
+            None => operations.new_error(ErrorType::DefaultCommodityDoesNotDefine, span, HashMap::default())?,
+        }
+    } else if !amount.currency.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        let commodity = operations.option(KEY_CURRENCY_SYMBOL)?.and_then(|option| {
+            option
+                .value
+                .split(',')
+                .filter_map(|mapping| mapping.split_once(':'))
+                .find(|(symbol, _)| symbol.trim().eq(&amount.currency))
+                .map(|(_, commodity)| commodity.trim().to_owned())
+        });
+        match commodity {
+            Some(commodity) => amount.currency = commodity,
+            None => operations.new_error(
+                ErrorType::CurrencySymbolDoesNotDefine,
+                span,
+                HashMap::of("currency_symbol", amount.currency.clone()),
+            )?,
+        }
+    }
+    Ok(())
+}
+
 impl DirectiveProcess for Options {
-    fn process(&mut self, ledger: &mut Ledger, _span: &SpanInfo) -> ZhangResult<()> {
+    fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
+        if self.key.as_str() == KEY_PRICE_STALENESS_DAYS && self.value.as_str().trim().parse::<u32>().is_err() {
+            operations.new_error(
+                ErrorType::InvalidOptionValue,
+                span,
+                HashMap::of2("key", self.key.as_str(), "value", self.value.as_str()),
+            )?;
+        }
         let option_value = ledger.options.parse(self.key.as_str(), self.value.as_str(), &mut operations)?;
         operations.insert_or_update_options(self.key.as_str(), option_value.as_str())?;
         Ok(())
@@ -81,6 +151,7 @@ impl DirectiveProcess for Open {
             self.account.clone(),
             AccountStatus::Open,
             self.meta.get_one("alias").map(|it| it.as_str()),
+            defined_in(ledger, span),
         )?;
 
         operations.insert_meta(MetaType::AccountMeta, self.account.name(), self.meta.clone())?;
@@ -113,15 +184,15 @@ impl DirectiveProcess for Commodity {
         let default_precision = operations.option(KEY_DEFAULT_COMMODITY_PRECISION)?.map(|it| it.value);
         let default_rounding = operations.option(KEY_DEFAULT_ROUNDING)?.map(|it| it.value);
 
-        let precision = self
-            .meta
-            .get_one("precision")
-            .map(|it| it.as_str().to_owned())
-            .or(default_precision)
-            .map(|it| it.as_str().parse::<i32>())
-            .transpose()
-            .unwrap_or(None)
-            .unwrap_or(DEFAULT_COMMODITY_PRECISION);
+        let meta_precision = |key: &str| self.meta.get_one(key).and_then(|it| it.as_str().parse::<i32>().ok());
+        let default_precision = default_precision.and_then(|it| it.parse::<i32>().ok()).unwrap_or(DEFAULT_COMMODITY_PRECISION);
+        // balance precision and display precision fall back to each other if only one of them is set
+        let precision = meta_precision("precision")
+            .or_else(|| meta_precision("display_precision"))
+            .unwrap_or(default_precision);
+        let display_precision = meta_precision("display_precision")
+            .or_else(|| meta_precision("precision"))
+            .unwrap_or(default_precision);
         let prefix = self.meta.get_one("prefix").map(|it| it.clone().to_plain_string());
         let suffix = self.meta.get_one("suffix").map(|it| it.clone().to_plain_string());
         let rounding = self
@@ -133,7 +204,7 @@ impl DirectiveProcess for Commodity {
             .transpose()
             .unwrap_or(None);
 
-        operations.insert_commodity(&self.currency, precision, prefix, suffix, rounding.map(|it| it.to_string()))?;
+        operations.insert_commodity(&self.currency, precision, display_precision, prefix, suffix, rounding.map(|it| it.to_string()))?;
         operations.insert_meta(MetaType::CommodityMeta, &self.currency, self.meta.clone())?;
 
         Ok(())
@@ -144,9 +215,28 @@ impl DirectiveProcess for Transaction {
     fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
 
-        if self.flag != Some(Flag::BalancePad) && self.flag != Some(Flag::BalanceCheck) && !ledger.is_transaction_balanced(self)? {
-            operations.new_error(ErrorType::TransactionDoesNotBalance, span, HashMap::default())?;
+        for posting in self.postings.iter_mut() {
+            if let Some(units) = posting.units.as_mut() {
+                resolve_commodity(units, ledger, span)?;
+            }
+            if let Some(cost) = posting.cost.as_mut() {
+                resolve_commodity(cost, ledger, span)?;
+            }
+            match posting.price.as_mut() {
+                Some(SingleTotalPrice::Single(price)) | Some(SingleTotalPrice::Total(price)) => resolve_commodity(price, ledger, span)?,
+                None => {}
+            }
         }
+
+        let rounding_residual = if self.flag != Some(Flag::BalancePad) && self.flag != Some(Flag::BalanceCheck) {
+            let rounding_residual = ledger.transaction_rounding_residual(self)?;
+            if rounding_residual.is_none() {
+                operations.new_error(ErrorType::TransactionDoesNotBalance, span, HashMap::default())?;
+            }
+            rounding_residual
+        } else {
+            None
+        };
         let id = Uuid::from_span(span);
         let sequence = ledger.trx_counter.fetch_add(1, Ordering::Relaxed);
         operations.insert_transaction(
@@ -158,6 +248,7 @@ impl DirectiveProcess for Transaction {
             self.narration.as_ref().map(|it| it.as_str()),
             self.tags.iter().cloned().collect_vec(),
             self.links.iter().cloned().collect_vec(),
+            rounding_residual,
             span,
         )?;
 
@@ -194,17 +285,53 @@ impl DirectiveProcess for Transaction {
             }
 
             let amount = txn_posting.units().unwrap_or_else(|| txn_posting.infer_trade_amount().unwrap());
-            let lot_info = txn_posting.lots().unwrap_or(LotInfo::Fifo);
-            lot_add(txn_posting.account_name(), amount, lot_info, &mut operations)?;
+            let account_name = txn_posting.account_name();
+            if txn_posting.is_cost_adjustment() {
+                let adjustment = txn_posting.posting.cost.clone().expect("cost adjustment posting has cost");
+                operations.adjust_account_lots_cost(&account_name, &amount.currency, &adjustment)?;
+            } else if amount.number.is_negative()
+                && txn_posting.posting.cost.is_none()
+                && operations.has_account_lots_with_cost(&account_name, &amount.currency)?
+            {
+                // selling commodity with cost, reduce the lots by booking method and calculate the realized gain with the sell price
+                let booking_method = account_booking_method(&account_name, ledger)?;
+                let reduced_lots = operations.reduce_account_lots(&account_name, &amount.currency, &amount.number.abs(), booking_method)?;
+                if let Some(price) = txn_posting.costs() {
+                    let gain: BigDecimal = reduced_lots
+                        .iter()
+                        .filter(|(cost, _)| cost.currency.eq(&price.currency))
+                        .map(|(cost, number)| (&price.number).sub(&cost.number).mul(number))
+                        .sum();
+                    operations.insert_realized_gain(
+                        &id,
+                        self.date.to_timezone_datetime(&ledger.options.timezone),
+                        &account_name,
+                        &amount.currency,
+                        Amount::new(gain, price.currency),
+                    )?;
+                }
+            } else {
+                let lot_info = txn_posting.lots().unwrap_or(LotInfo::Fifo);
+                // the date in cost braces is the acquisition date of lot, which may be earlier than the booking date of transaction
+                let acquisition_date = txn_posting.posting.cost_date.as_ref().unwrap_or(&self.date);
+                lot_add(
+                    account_name,
+                    amount,
+                    lot_info,
+                    acquisition_date.to_timezone_datetime(&ledger.options.timezone),
+                    &mut operations,
+                )?;
+            }
         }
         for document in self.meta.clone().get_flatten().into_iter().filter(|(key, _)| key.eq("document")) {
             let (_, document_file_name) = document;
-            let document_path = document_file_name.to_plain_string();
-            let document_pathbuf = PathBuf::from(&document_path);
+            let Some(document_pathbuf) = resolve_document_path(&document_file_name.to_plain_string(), ledger, span)? else {
+                continue;
+            };
             operations.insert_document(
                 self.date.to_timezone_datetime(&ledger.options.timezone),
                 document_pathbuf.file_name().and_then(|it| it.to_str()),
-                document_path,
+                document_pathbuf.to_string_lossy().to_string(),
                 DocumentType::Trx(id),
             )?;
         }
@@ -216,6 +343,7 @@ impl DirectiveProcess for Transaction {
 impl DirectiveProcess for BalancePad {
     fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
+        resolve_commodity(&mut self.amount, ledger, span)?;
         check_account_existed(self.account.name(), ledger, span)?;
         check_account_existed(self.pad.name(), ledger, span)?;
         check_account_closed(self.account.name(), ledger, span)?;
@@ -272,6 +400,7 @@ impl DirectiveProcess for BalancePad {
 impl DirectiveProcess for BalanceCheck {
     fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
+        resolve_commodity(&mut self.amount, ledger, span)?;
         let option = operations.account_target_day_balance(
             self.account.name(),
             self.date.to_timezone_datetime(&ledger.options.timezone),
@@ -323,13 +452,13 @@ impl DirectiveProcess for Document {
         check_account_existed(self.account.name(), ledger, span)?;
         check_account_closed(self.account.name(), ledger, span)?;
 
-        let path = self.filename.clone().to_plain_string();
-
-        let document_pathbuf = PathBuf::from(&path);
+        let Some(document_pathbuf) = resolve_document_path(&self.filename.clone().to_plain_string(), ledger, span)? else {
+            return Ok(());
+        };
         operations.insert_document(
             self.date.to_timezone_datetime(&ledger.options.timezone),
             document_pathbuf.file_name().and_then(|it| it.to_str()),
-            path,
+            document_pathbuf.to_string_lossy().to_string(),
             DocumentType::Account(self.account.clone()),
         )?;
         Ok(())
@@ -339,6 +468,7 @@ impl DirectiveProcess for Document {
 impl DirectiveProcess for Price {
     fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
+        resolve_commodity(&mut self.amount, ledger, span)?;
         check_commodity_define(&self.currency, ledger, span)?;
         check_commodity_define(&self.amount.currency, ledger, span)?;
         operations.insert_price(
@@ -372,6 +502,7 @@ impl DirectiveProcess for Budget {
 impl DirectiveProcess for BudgetAdd {
     fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
+        resolve_commodity(&mut self.amount, ledger, span)?;
         if !operations.contains_budget(&self.name) {
             operations.new_error(ErrorType::BudgetDoesNotExist, span, HashMap::default())?;
         } else {
@@ -388,8 +519,9 @@ impl DirectiveProcess for BudgetAdd {
 }
 
 impl DirectiveProcess for BudgetTransfer {
-    fn process(&mut self, ledger: &mut Ledger, _span: &SpanInfo) -> ZhangResult<()> {
+    fn process(&mut self, ledger: &mut Ledger, span: &SpanInfo) -> ZhangResult<()> {
         let mut operations = ledger.operations();
+        resolve_commodity(&mut self.amount, ledger, span)?;
         // todo: check if budget exists
         operations.budget_transfer(
             self.date.to_timezone_datetime(&ledger.options.timezone),
@@ -412,33 +544,35 @@ impl DirectiveProcess for BudgetClose {
     }
 }
 
-fn lot_add(account_name: AccountName, amount: Amount, lot_info: LotInfo, operations: &mut Operations) -> ZhangResult<()> {
+/// add amount into the lot of account, `acquisition_datetime` is the datetime of lot with cost, which is used to order lots in booking.
+fn lot_add(account_name: AccountName, amount: Amount, lot_info: LotInfo, acquisition_datetime: DateTime<Tz>, operations: &mut Operations) -> ZhangResult<()> {
     match lot_info {
         LotInfo::Lot(target_currency, lot_number) => {
             let price = Amount::new(lot_number, target_currency);
+            let datetime = Some(acquisition_datetime);
 
-            let lot = operations.account_lot(&account_name, &amount.currency, Some(price.clone()))?;
+            let lot = operations.account_lot(&account_name, &amount.currency, Some(price.clone()), datetime)?;
 
             if let Some(lot_row) = lot {
-                operations.update_account_lot(&account_name, &amount.currency, Some(price), &lot_row.amount.add(&amount.number))?;
+                operations.update_account_lot(&account_name, &amount.currency, Some(price), datetime, &lot_row.amount.add(&amount.number))?;
             } else {
-                operations.insert_account_lot(&account_name, &amount.currency, Some(price.clone()), &amount.number)?;
+                operations.insert_account_lot(&account_name, &amount.currency, Some(price.clone()), datetime, &amount.number)?;
             }
         }
         LotInfo::Fifo => {
-            let lot = operations.account_lot(&account_name, &amount.currency, None)?;
+            let lot = operations.account_lot(&account_name, &amount.currency, None, None)?;
             if let Some(lot) = lot {
                 if lot.price.is_some() {
                     // target lot
-                    operations.update_account_lot(&account_name, &amount.currency, lot.price, &lot.amount.add(&amount.number))?;
+                    operations.update_account_lot(&account_name, &amount.currency, lot.price, lot.datetime, &lot.amount.add(&amount.number))?;
 
                     // todo check negative
                 } else {
                     // default lot
-                    operations.update_account_lot(&account_name, &amount.currency, None, &lot.amount.add(&amount.number))?;
+                    operations.update_account_lot(&account_name, &amount.currency, None, None, &lot.amount.add(&amount.number))?;
                 }
             } else {
-                operations.insert_account_lot(&account_name, &amount.currency, None, &amount.number)?;
+                operations.insert_account_lot(&account_name, &amount.currency, None, None, &amount.number)?;
             }
         }
         LotInfo::Filo => {
@@ -5,7 +5,7 @@ use chrono::DateTime;
 use chrono_tz::Tz;
 use uuid::Uuid;
 use zhang_ast::amount::Amount;
-use zhang_ast::{Account, Flag, SpanInfo};
+use zhang_ast::{Account, AccountType, Flag, SpanInfo};
 
 use crate::domains::schemas::{AccountDomain, CommodityDomain, ErrorDomain, MetaDomain, PriceDomain};
 
@@ -24,6 +24,8 @@ pub struct Store {
     // by account
     pub commodity_lots: HashMap<String, Vec<CommodityLotRecord>>,
 
+    pub realized_gains: Vec<RealizedGainDomain>,
+
     pub documents: Vec<DocumentDomain>,
 
     pub metas: Vec<MetaDomain>,
@@ -42,6 +44,9 @@ pub struct TransactionDomain {
     pub span: SpanInfo,
     pub tags: Vec<String>,
     pub links: Vec<String>,
+    /// the pre-rounding imbalance of each currency absorbed by tolerance,
+    /// `None` if transaction does not balance or is generated by balance check and balance pad
+    pub rounding_residual: Option<Vec<Amount>>,
     pub postings: Vec<PostingDomain>,
 }
 
@@ -65,6 +70,17 @@ impl TransactionDomain {
             is_any_posting_account_matched
         })
     }
+
+    /// transfer moves money between own accounts, which means all postings are in assets or liabilities accounts.
+    /// it is neither income nor expense, and generated balance transactions are never transfers.
+    pub fn is_transfer(&self) -> bool {
+        !matches!(self.flag, Flag::BalancePad | Flag::BalanceCheck)
+            && !self.postings.is_empty()
+            && self
+                .postings
+                .iter()
+                .all(|posting| matches!(posting.account.account_type, AccountType::Assets | AccountType::Liabilities))
+    }
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -124,6 +140,16 @@ pub struct CommodityLotRecord {
     pub price: Option<Amount>,
 }
 
+/// the gain realized by selling commodity with cost, which is calculated by the sell price and the cost of reduced lots
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RealizedGainDomain {
+    pub trx_id: Uuid,
+    pub datetime: DateTime<Tz>,
+    pub account: String,
+    pub commodity: String,
+    pub amount: Amount,
+}
+
 #[derive(Clone, Debug, serde::Serialize)]
 pub struct BudgetDomain {
     pub name: String,
@@ -144,6 +170,25 @@ pub struct BudgetIntervalDetail {
     pub activity_amount: Amount,
 }
 
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct BudgetRollupDetail {
+    /// year and month pair, calculated as `year*100+month`, E.G. `202312`
+    pub date: u32,
+    /// names of budgets which are rolled up
+    pub budgets: Vec<String>,
+    /// amounts are added up per commodity, since budgets in different commodities cannot be summed
+    pub amounts: Vec<BudgetRollupAmount>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BudgetRollupAmount {
+    pub commodity: String,
+    /// names of budgets in this commodity
+    pub budgets: Vec<String>,
+    pub assigned_amount: Amount,
+    pub activity_amount: Amount,
+}
+
 #[derive(Clone, Debug, serde::Serialize)]
 pub struct BudgetEvent {
     pub datetime: DateTime<Tz>,
@@ -1,5 +1,10 @@
 use std::path::PathBuf;
 
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+
+use crate::{ZhangError, ZhangResult};
+
 pub mod bigdecimal_ext;
 pub mod calculable;
 pub mod date_range;
@@ -12,3 +17,13 @@ pub mod string_;
 pub fn has_path_visited<'a>(visited: impl IntoIterator<Item = &'a PathBuf>, path: &PathBuf) -> bool {
     visited.into_iter().any(|pathbuf| pathbuf.eq(path))
 }
+
+/// resolve the local datetime in given timezone, the earliest one is taken for ambiguous time,
+/// and the time skipped by daylight saving gap is shifted by an hour to land after the gap.
+pub fn local_datetime(timezone: &Tz, naive: NaiveDateTime) -> ZhangResult<DateTime<Tz>> {
+    timezone
+        .from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| timezone.from_local_datetime(&(naive + Duration::hours(1))).earliest())
+        .ok_or(ZhangError::InvalidDate)
+}
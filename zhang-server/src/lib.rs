@@ -39,6 +39,7 @@ use routes::commodity::*;
 use routes::common::*;
 use routes::document::*;
 use routes::file::*;
+use routes::metrics::*;
 use routes::statistics::*;
 use routes::transaction::*;
 use zhang_core::data_source::DataSource;
@@ -259,17 +260,22 @@ pub fn create_server_app(
         .route("/api/sse", get(sse))
         .route("/api/reload", post(reload))
         .route("/api/info", get(get_basic_info))
+        .route("/api/date-range", get(get_date_range))
         .route("/api/store", get(get_store_data))
         .route("/api/options", get(get_all_options))
         .route("/api/errors", get(get_errors))
         .route("/api/files", get(get_files))
+        .route("/api/files/tree", get(get_file_tree))
         .route("/api/files/:file_path", get(get_file_content))
         .route("/api/files/:file_path", put(update_file_content))
         .route("/api/for-new-transaction", get(get_info_for_new_transactions))
         .route("/api/journals", get(get_journals))
         .route("/api/transactions", post(create_new_transaction))
+        .route("/api/transactions/convert", post(convert_transaction))
+        .route("/api/transactions/annotate", post(annotate_transaction))
         .route("/api/transactions/:transaction_id/documents", post(upload_transaction_document))
         .route("/api/accounts", get(get_account_list))
+        .route("/api/accounts/search", get(search_accounts))
         .route("/api/accounts/:account_name", get(get_account_info))
         .route("/api/accounts/:account_name/documents", post(upload_account_document))
         .route("/api/accounts/:account_name/documents", get(get_account_documents))
@@ -280,12 +286,18 @@ pub fn create_server_app(
         .route("/api/documents/:file_path", get(download_document))
         .route("/api/commodities", get(get_all_commodities))
         .route("/api/commodities/:commodity_name", get(get_single_commodity))
+        .route("/api/stale-prices", get(get_stale_prices))
         .route("/api/statistic/summary", get(get_statistic_summary))
         .route("/api/statistic/graph", get(get_statistic_graph))
         .route("/api/statistic/:account_type", get(get_statistic_rank_detail_by_account_type))
+        .route("/api/financial-statements", get(get_financial_statements))
+        .route("/api/meta-sum", get(get_meta_sum))
+        .route("/api/average-monthly-spend", get(get_average_monthly_spend))
         .route("/api/budgets", get(get_budget_list))
+        .route("/api/budgets/rollup", get(get_budget_rollup))
         .route("/api/budgets/:budget_name", get(get_budget_info))
         .route("/api/budgets/:budget_name/interval/:year/:month", get(get_budget_interval_detail))
+        .route("/metrics", get(get_metrics))
         .layer(CorsLayer::permissive())
         .layer(DefaultBodyLimit::disable())
         .layer(RequestBodyLimitLayer::new(250 * 1024 * 1024 /* 250mb */))
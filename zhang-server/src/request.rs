@@ -3,6 +3,7 @@ use std::cmp::max;
 use bigdecimal::BigDecimal;
 use chrono::{DateTime, Datelike, Local, Utc};
 use serde::Deserialize;
+use zhang_core::store::TransactionDomain;
 
 #[derive(Deserialize)]
 #[serde(tag = "type")]
@@ -11,6 +12,18 @@ pub enum AccountBalanceRequest {
     Pad { account_name: String, amount: AmountRequest, pad: String },
 }
 
+#[derive(Deserialize)]
+pub struct AccountSearchRequest {
+    pub query: String,
+    pub limit: Option<usize>,
+}
+
+impl AccountSearchRequest {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(20)
+    }
+}
+
 #[derive(Deserialize)]
 pub struct FileUpdateRequest {
     pub content: String,
@@ -27,12 +40,63 @@ pub enum StatisticInterval {
 pub struct StatisticRequest {
     pub from: DateTime<Utc>,
     pub to: DateTime<Utc>,
+    /// exclude transfers between own accounts from the cash flow
+    pub exclude_transfers: Option<bool>,
 }
 #[derive(Deserialize)]
 pub struct StatisticGraphRequest {
     pub from: DateTime<Utc>,
     pub to: DateTime<Utc>,
     pub interval: StatisticInterval,
+    /// exclude transfers between own accounts from the cash flow
+    pub exclude_transfers: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentTypeRequest {
+    Account,
+    Transaction,
+}
+
+#[derive(Deserialize)]
+pub struct DocumentRequest {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(rename = "type")]
+    pub document_type: Option<DocumentTypeRequest>,
+}
+
+#[derive(Deserialize)]
+pub struct FinancialStatementRequest {
+    /// the datetime of balance sheet
+    pub as_of: DateTime<Utc>,
+    /// the period of income statement
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct MetaSumRequest {
+    /// the key of transaction meta
+    pub key: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct AverageMonthlySpendRequest {
+    /// the expense account, spending of its sub-accounts is included
+    pub category: String,
+    pub months: u32,
+    /// the trailing months are counted before the month of this datetime, using now if not present
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct StalePriceRequest {
+    /// the staleness of prices is measured until this datetime, using now if not present
+    pub as_of: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize)]
@@ -46,8 +110,34 @@ pub struct JournalRequest {
     pub page: Option<u32>,
     pub size: Option<u32>,
     pub keyword: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// comma separated tags, transaction should contain all of them
+    pub tags: Option<String>,
+    pub payee: Option<String>,
+    pub flag: Option<String>,
 }
 impl JournalRequest {
+    /// all filters are combined with AND, and missing filter means no constraint
+    pub fn is_matched(&self, trx: &TransactionDomain) -> bool {
+        let datetime = trx.datetime.with_timezone(&Utc);
+        self.keyword.as_ref().map(|keyword| trx.contains_keyword(keyword)).unwrap_or(true)
+            && self.from.map(|from| datetime >= from).unwrap_or(true)
+            && self.to.map(|to| datetime <= to).unwrap_or(true)
+            && self
+                .tags
+                .as_ref()
+                .map(|tags| {
+                    tags.split(',')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .all(|tag| trx.tags.iter().any(|it| it.eq(tag)))
+                })
+                .unwrap_or(true)
+            && self.payee.as_ref().map(|payee| trx.payee.as_ref() == Some(payee)).unwrap_or(true)
+            && self.flag.as_ref().map(|flag| trx.flag.to_string().eq(flag)).unwrap_or(true)
+    }
+
     pub fn page(&self) -> u32 {
         max(self.page.unwrap_or(1), 1)
     }
@@ -83,6 +173,28 @@ pub struct AmountRequest {
     pub commodity: String,
 }
 
+#[derive(Deserialize)]
+pub struct SpanRequest {
+    pub filename: String,
+    pub start: usize,
+}
+
+#[derive(Deserialize)]
+pub struct ConvertTransactionRequest {
+    pub span: SpanRequest,
+    /// the currency to be converted, which can be omitted if the transaction only has one currency other than target one
+    pub from: Option<String>,
+    pub commodity: String,
+    pub rate: BigDecimal,
+}
+
+#[derive(Deserialize)]
+pub struct AnnotateTransactionRequest {
+    pub span: SpanRequest,
+    pub key: String,
+    pub value: String,
+}
+
 #[derive(Deserialize)]
 pub struct MetaRequest {
     pub key: String,
@@ -96,7 +208,25 @@ pub struct BudgetListRequest {
 }
 impl BudgetListRequest {
     pub fn as_interval(&self) -> u32 {
-        let time = Local::now();
-        self.year.unwrap_or(time.year() as u32) * 100 + self.month.unwrap_or(time.month())
+        budget_interval(self.year, self.month)
+    }
+}
+
+/// the budget interval `year*100+month`, the current year and month are used if not given
+fn budget_interval(year: Option<u32>, month: Option<u32>) -> u32 {
+    let time = Local::now();
+    year.unwrap_or(time.year() as u32) * 100 + month.unwrap_or(time.month())
+}
+
+#[derive(Deserialize)]
+pub struct BudgetRollupRequest {
+    /// the parent account, budgets of its sub-accounts are rolled up
+    pub account: String,
+    pub month: Option<u32>,
+    pub year: Option<u32>,
+}
+impl BudgetRollupRequest {
+    pub fn as_interval(&self) -> u32 {
+        budget_interval(self.year, self.month)
     }
 }
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use axum::response::{IntoResponse, Response};
 use axum::Json;
@@ -8,7 +9,7 @@ use serde::Serialize;
 use uuid::Uuid;
 use zhang_ast::amount::{Amount, CalculatedAmount};
 use zhang_ast::AccountType;
-use zhang_core::domains::schemas::{AccountJournalDomain, AccountStatus, MetaDomain};
+use zhang_core::domains::schemas::{AccountJournalDomain, AccountStatus, BalanceSheetDomain, IncomeStatementDomain, MetaDomain};
 use zhang_core::store::BudgetEvent;
 
 use crate::ServerResult;
@@ -59,7 +60,7 @@ pub struct Pageable<T: Serialize> {
 
 impl<T: Serialize> Pageable<T> {
     pub fn new(total_count: u32, page: u32, size: u32, records: Vec<T>) -> Self {
-        let total_page = total_count / size + u32::from(total_count % size != 0);
+        let total_page = total_count / size + u32::from(!total_count.is_multiple_of(size));
         Self {
             total_count,
             total_page,
@@ -75,6 +76,7 @@ pub struct AccountResponse {
     pub name: String,
     pub status: AccountStatus,
     pub alias: Option<String>,
+    pub defined_in: Option<PathBuf>,
     pub amount: CalculatedAmount,
 }
 
@@ -144,6 +146,9 @@ pub struct JournalTransactionItemResponse {
     pub links: Vec<String>,
     pub flag: String,
     pub is_balanced: bool,
+    pub is_transfer: bool,
+    /// the imbalance of each currency absorbed by rounding tolerance, null if transaction does not balance
+    pub rounding_residual: Option<Vec<AmountResponse>>,
     pub postings: Vec<JournalTransactionPostingResponse>,
     pub metas: Vec<MetaResponse>,
 }
@@ -162,6 +167,23 @@ pub struct JournalTransactionPostingResponse {
     pub account_after_commodity: String,
 }
 
+#[derive(Serialize)]
+pub struct TransactionResponse {
+    pub datetime: NaiveDateTime,
+    pub payee: String,
+    pub narration: Option<String>,
+    pub tags: Vec<String>,
+    pub links: Vec<String>,
+    pub flag: String,
+    pub postings: Vec<TransactionPostingResponse>,
+}
+
+#[derive(Serialize)]
+pub struct TransactionPostingResponse {
+    pub account: String,
+    pub unit: Option<AmountResponse>,
+}
+
 #[derive(Serialize)]
 pub struct JournalBalanceCheckItemResponse {
     pub id: Uuid,
@@ -209,6 +231,7 @@ impl From<Amount> for AmountResponse {
 pub struct CommodityListItemResponse {
     pub name: String,
     pub precision: i32,
+    pub balance_precision: i32,
     pub prefix: Option<String>,
     pub suffix: Option<String>,
     pub rounding: Option<String>,
@@ -247,6 +270,47 @@ pub struct FileDetailResponse {
     pub content: String,
 }
 
+#[derive(Serialize)]
+pub struct FileTreeNodeResponse {
+    pub name: String,
+    /// the path relative to ledger root
+    pub path: String,
+    pub is_dir: bool,
+    pub children: Vec<FileTreeNodeResponse>,
+}
+
+impl FileTreeNodeResponse {
+    /// insert the file into the tree by its path components, creating the missing directory nodes along the way
+    pub(crate) fn insert(nodes: &mut Vec<FileTreeNodeResponse>, parent: &str, components: &[String]) {
+        let Some((name, rest)) = components.split_first() else {
+            return;
+        };
+        let path = if parent.is_empty() { name.clone() } else { format!("{}/{}", parent, name) };
+        let is_dir = !rest.is_empty();
+        let index = match nodes.iter().position(|node| node.name.eq(name) && node.is_dir == is_dir) {
+            Some(index) => index,
+            None => {
+                nodes.push(FileTreeNodeResponse {
+                    name: name.clone(),
+                    path: path.clone(),
+                    is_dir,
+                    children: vec![],
+                });
+                nodes.len() - 1
+            }
+        };
+        FileTreeNodeResponse::insert(&mut nodes[index].children, &path, rest);
+    }
+
+    /// sort nodes recursively, directories go before files and both are sorted by name
+    pub(crate) fn sort(nodes: &mut [FileTreeNodeResponse]) {
+        nodes.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+        for node in nodes.iter_mut() {
+            FileTreeNodeResponse::sort(&mut node.children);
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct StatisticSummaryResponse {
     pub from: DateTime<Utc>,
@@ -309,6 +373,12 @@ pub struct ReportRankItemResponse {
     pub amount: CalculatedAmount,
 }
 
+#[derive(Serialize)]
+pub struct DateRangeResponse {
+    pub min: Option<DateTime<Utc>>,
+    pub max: Option<DateTime<Utc>>,
+}
+
 #[derive(Serialize)]
 pub struct BasicInfo {
     pub title: Option<String>,
@@ -323,6 +393,7 @@ pub struct AccountInfoResponse {
     pub name: String,
     pub status: AccountStatus,
     pub alias: Option<String>,
+    pub defined_in: Option<PathBuf>,
     pub amount: CalculatedAmount,
 }
 
@@ -337,6 +408,22 @@ pub struct BudgetListItemResponse {
     pub available_amount: Amount,
 }
 
+#[derive(Serialize)]
+pub struct BudgetRollupResponse {
+    pub account: String,
+    pub budgets: Vec<String>,
+    pub amounts: Vec<BudgetRollupAmountResponse>,
+}
+
+#[derive(Serialize)]
+pub struct BudgetRollupAmountResponse {
+    pub commodity: String,
+    pub budgets: Vec<String>,
+    pub assigned_amount: Amount,
+    pub activity_amount: Amount,
+    pub available_amount: Amount,
+}
+
 #[derive(Serialize)]
 pub struct BudgetInfoResponse {
     pub name: String,
@@ -366,3 +453,15 @@ impl BudgetIntervalEventResponse {
         }
     }
 }
+
+#[derive(Serialize)]
+pub struct FinancialStatementsResponse {
+    pub balance_sheet: BalanceSheetDomain,
+    pub income_statement: IncomeStatementDomain,
+}
+
+#[derive(Serialize)]
+pub struct MetaSumResponse {
+    pub key: String,
+    pub total: BigDecimal,
+}
@@ -1,7 +1,8 @@
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use axum::extract::{Multipart, Path, State};
+use axum::extract::{Multipart, Path, Query, State};
 use axum::Json;
 use chrono::Utc;
 use itertools::Itertools;
@@ -10,11 +11,11 @@ use tokio::sync::RwLock;
 use uuid::Uuid;
 use zhang_ast::amount::Amount;
 use zhang_ast::{Account, BalanceCheck, BalancePad, Date, Directive, Document, ZhangString};
-use zhang_core::domains::schemas::AccountJournalDomain;
+use zhang_core::domains::schemas::{AccountDomain, AccountJournalDomain};
 use zhang_core::ledger::Ledger;
 use zhang_core::utils::calculable::Calculable;
 
-use crate::request::AccountBalanceRequest;
+use crate::request::{AccountBalanceRequest, AccountSearchRequest};
 use crate::response::{AccountInfoResponse, AccountResponse, DocumentResponse, ResponseWrapper};
 use crate::{ApiResult, ReloadSender};
 
@@ -37,12 +38,20 @@ pub async fn get_account_list(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResult<V
             name: account,
             status: account_domain.status,
             alias: account_domain.alias,
+            defined_in: account_domain.defined_in,
             amount,
         });
     }
     ResponseWrapper::json(ret)
 }
 
+pub async fn search_accounts(ledger: State<Arc<RwLock<Ledger>>>, params: Query<AccountSearchRequest>) -> ApiResult<Vec<AccountDomain>> {
+    let ledger = ledger.read().await;
+    let mut operations = ledger.operations();
+    let accounts = operations.search_accounts(&params.query, params.limit())?;
+    ResponseWrapper::json(accounts)
+}
+
 pub async fn get_account_info(ledger: State<Arc<RwLock<Ledger>>>, path: Path<(String,)>) -> ApiResult<AccountInfoResponse> {
     let account_name = path.0 .0;
     let ledger = ledger.read().await;
@@ -67,6 +76,7 @@ pub async fn get_account_info(ledger: State<Arc<RwLock<Ledger>>>, path: Path<(St
         name: account_info.name,
         status: account_info.status,
         alias: account_info.alias,
+        defined_in: account_info.defined_in,
         amount,
     })
 }
@@ -76,7 +86,6 @@ pub async fn upload_account_document(
 ) -> ApiResult<()> {
     let account_name = path.0 .0;
     let ledger_stage = ledger.read().await;
-    let entry = &ledger_stage.entry.0;
     let mut documents = vec![];
 
     while let Some(field) = multipart.next_field().await.unwrap() {
@@ -85,29 +94,27 @@ pub async fn upload_account_document(
         let _content_type = field.content_type().unwrap().to_string();
 
         let v4 = Uuid::new_v4();
-        let buf = entry.join("attachments").join(v4.to_string()).join(&file_name);
-        let striped_buf = buf.strip_prefix(entry).unwrap();
+        // the document filename is relative to documents directory, while the saving path is relative to entry directory
+        let document_path = PathBuf::from("attachments").join(v4.to_string()).join(&file_name).to_string_lossy().to_string();
+        let striped_buf = ledger_stage.resolve_document_path(&document_path)?;
         info!("uploading document `{}`(id={}) to account {}", file_name, &v4.to_string(), &account_name);
 
         let content_buf = field.bytes().await.unwrap();
 
         let striped_path_string = striped_buf.to_string_lossy().to_string();
-        ledger_stage
-            .data_source
-            .async_save(&ledger_stage, striped_path_string.to_owned(), &content_buf)
-            .await?;
+        ledger_stage.data_source.async_save(&ledger_stage, striped_path_string, &content_buf).await?;
 
         documents.push(Directive::Document(Document {
             date: Date::now(&ledger_stage.options.timezone),
             account: Account::from_str(&account_name)?,
-            filename: ZhangString::QuoteString(striped_path_string),
+            filename: ZhangString::QuoteString(document_path),
             tags: None,
             links: None,
             meta: Default::default(),
         }));
     }
 
-    ledger_stage.data_source.async_append(&ledger_stage, documents).await?;
+    ledger_stage.async_append(documents).await?;
     reload_sender.reload();
     ResponseWrapper::<()>::created()
 }
@@ -175,7 +182,7 @@ pub async fn create_account_balance(
         }),
     };
 
-    ledger.data_source.async_append(&ledger, vec![balance]).await.unwrap();
+    ledger.async_append(vec![balance]).await?;
     reload_sender.reload();
     ResponseWrapper::<()>::created()
 }
@@ -210,7 +217,7 @@ pub async fn create_batch_account_balances(
         directives.push(balance);
     }
 
-    ledger.data_source.async_append(&ledger, directives).await?;
+    ledger.async_append(directives).await?;
     reload_sender.reload();
     ResponseWrapper::<()>::created()
 }
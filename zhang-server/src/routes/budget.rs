@@ -11,8 +11,10 @@ use zhang_ast::amount::Amount;
 use zhang_core::ledger::Ledger;
 use zhang_core::store::BudgetIntervalDetail;
 
-use crate::request::BudgetListRequest;
-use crate::response::{BudgetInfoResponse, BudgetIntervalEventResponse, BudgetListItemResponse, ResponseWrapper};
+use crate::request::{BudgetListRequest, BudgetRollupRequest};
+use crate::response::{
+    BudgetInfoResponse, BudgetIntervalEventResponse, BudgetListItemResponse, BudgetRollupAmountResponse, BudgetRollupResponse, ResponseWrapper,
+};
 use crate::ApiResult;
 
 pub async fn get_budget_list(ledger: State<Arc<RwLock<Ledger>>>, params: Query<BudgetListRequest>) -> ApiResult<Vec<BudgetListItemResponse>> {
@@ -38,6 +40,32 @@ pub async fn get_budget_list(ledger: State<Arc<RwLock<Ledger>>>, params: Query<B
     ResponseWrapper::json(ret)
 }
 
+pub async fn get_budget_rollup(ledger: State<Arc<RwLock<Ledger>>>, params: Query<BudgetRollupRequest>) -> ApiResult<BudgetRollupResponse> {
+    let interval = params.as_interval();
+
+    let ledger = ledger.read().await;
+    let operations = ledger.operations();
+
+    let Some(rollup) = operations.budget_rollup_month_detail(&params.account, interval)? else {
+        return ResponseWrapper::not_found();
+    };
+    ResponseWrapper::json(BudgetRollupResponse {
+        account: params.account.clone(),
+        budgets: rollup.budgets,
+        amounts: rollup
+            .amounts
+            .into_iter()
+            .map(|amount| BudgetRollupAmountResponse {
+                commodity: amount.commodity,
+                budgets: amount.budgets,
+                available_amount: amount.assigned_amount.sub(amount.activity_amount.number.clone()),
+                assigned_amount: amount.assigned_amount,
+                activity_amount: amount.activity_amount,
+            })
+            .collect_vec(),
+    })
+}
+
 pub async fn get_budget_info(ledger: State<Arc<RwLock<Ledger>>>, paths: Path<(String,)>, params: Query<BudgetListRequest>) -> ApiResult<BudgetInfoResponse> {
     let (budget_name,) = paths.0;
     let ledger = ledger.read().await;
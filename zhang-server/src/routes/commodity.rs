@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
+use chrono::Utc;
 use itertools::Itertools;
 use tokio::sync::RwLock;
-use zhang_core::domains::schemas::CommodityDomain;
+use zhang_core::domains::schemas::{CommodityDomain, StalePriceDomain};
 use zhang_core::ledger::Ledger;
 
+use crate::request::StalePriceRequest;
 use crate::response::{CommodityDetailResponse, CommodityListItemResponse, CommodityLot, CommodityPrice, ResponseWrapper};
 use crate::ApiResult;
 
@@ -24,7 +26,8 @@ pub async fn get_all_commodities(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResul
 
         ret.push(CommodityListItemResponse {
             name: commodity.name,
-            precision: commodity.precision,
+            precision: commodity.display_precision,
+            balance_precision: commodity.precision,
             prefix: commodity.prefix,
             suffix: commodity.suffix,
             rounding: commodity.rounding,
@@ -50,7 +53,8 @@ pub async fn get_single_commodity(ledger: State<Arc<RwLock<Ledger>>>, params: Pa
     let amount = operations.get_commodity_balances(&commodity_name)?;
     let commodity_item = CommodityListItemResponse {
         name: commodity.name,
-        precision: commodity.precision,
+        precision: commodity.display_precision,
+        balance_precision: commodity.precision,
         prefix: commodity.prefix,
         suffix: commodity.suffix,
         rounding: commodity.rounding,
@@ -88,3 +92,12 @@ pub async fn get_single_commodity(ledger: State<Arc<RwLock<Ledger>>>, params: Pa
         prices,
     })
 }
+
+pub async fn get_stale_prices(ledger: State<Arc<RwLock<Ledger>>>, params: Query<StalePriceRequest>) -> ApiResult<Vec<StalePriceDomain>> {
+    let ledger = ledger.read().await;
+    let timezone = &ledger.options.timezone;
+    let mut operations = ledger.operations();
+
+    let as_of = params.as_of.unwrap_or_else(Utc::now).with_timezone(timezone);
+    ResponseWrapper::json(operations.stale_prices(as_of)?)
+}
@@ -5,6 +5,7 @@ use async_stream::try_stream;
 use axum::extract::{Query, State};
 use axum::response::sse::{Event, KeepAlive};
 use axum::response::Sse;
+use chrono::Utc;
 use futures_util::Stream;
 use itertools::Itertools;
 use tokio::sync::RwLock;
@@ -13,7 +14,7 @@ use zhang_core::ledger::Ledger;
 
 use crate::broadcast::Broadcaster;
 use crate::request::JournalRequest;
-use crate::response::{BasicInfo, Pageable, ResponseWrapper};
+use crate::response::{BasicInfo, DateRangeResponse, Pageable, ResponseWrapper};
 use crate::{ApiResult, ReloadSender};
 
 pub async fn backend_only_info() -> &'static str {
@@ -48,6 +49,17 @@ pub async fn get_basic_info(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResult<Bas
     })
 }
 
+pub async fn get_date_range(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResult<DateRangeResponse> {
+    let ledger = ledger.read().await;
+    let mut operations = ledger.operations();
+    let range = operations.transaction_datetime_range()?;
+
+    ResponseWrapper::json(DateRangeResponse {
+        min: range.map(|(min, _)| min.with_timezone(&Utc)),
+        max: range.map(|(_, max)| max.with_timezone(&Utc)),
+    })
+}
+
 pub async fn get_errors(ledger: State<Arc<RwLock<Ledger>>>, params: Query<JournalRequest>) -> ApiResult<Pageable<ErrorDomain>> {
     let ledger = ledger.read().await;
     let mut operations = ledger.operations();
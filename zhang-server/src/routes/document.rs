@@ -1,38 +1,53 @@
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::header;
 use axum::response::{AppendHeaders, IntoResponse};
 use bytes::Bytes;
 use itertools::Itertools;
 use tokio::sync::RwLock;
 use zhang_core::ledger::Ledger;
+use zhang_core::store::DocumentType;
+use zhang_core::ZhangError;
 
+use crate::request::{DocumentRequest, DocumentTypeRequest};
 use crate::response::{DocumentResponse, ResponseWrapper};
-use crate::ApiResult;
+use crate::{ApiResult, ServerResult};
 
-pub async fn download_document(ledger: State<Arc<RwLock<Ledger>>>, path: Path<(String,)>) -> impl IntoResponse {
+pub async fn download_document(ledger: State<Arc<RwLock<Ledger>>>, path: Path<(String,)>) -> ServerResult<impl IntoResponse> {
     let encoded_file_path = path.0 .0;
     let filename = String::from_utf8(base64::decode(encoded_file_path).unwrap()).unwrap();
     let ledger = ledger.read().await;
-    let entry = &ledger.entry.0;
-    let full_path = entry.join(filename);
-    let striped_path = full_path.strip_prefix(entry).unwrap();
+    // the path of document is relative to entry directory, it must be located in documents directory
+    let documents_base = ledger.documents_base()?;
+    let base_relative_path = std::path::Path::new(&filename)
+        .strip_prefix(&documents_base)
+        .map_err(|_| ZhangError::InvalidDocumentPath(filename.clone()))?;
+    let striped_path = ledger.resolve_document_path(&base_relative_path.to_string_lossy())?;
     let file_name = striped_path.file_name().unwrap().to_string_lossy().to_string();
-    let vec = ledger.data_source.async_get(striped_path.to_string_lossy().to_string()).await.unwrap();
+    let vec = ledger.data_source.async_get(striped_path.to_string_lossy().to_string()).await?;
     let bytes = Bytes::from(vec);
     let headers = AppendHeaders([(header::CONTENT_DISPOSITION, format!("inline; filename=\"{}\"", file_name))]);
-    (headers, bytes)
+    Ok((headers, bytes))
 }
 
-pub async fn get_documents(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResult<Vec<DocumentResponse>> {
+pub async fn get_documents(ledger: State<Arc<RwLock<Ledger>>>, params: Query<DocumentRequest>) -> ApiResult<Vec<DocumentResponse>> {
     let ledger = ledger.read().await;
     let operations = ledger.operations();
     let store = operations.read();
+    let params = params.0;
 
     let rows = store
         .documents
         .iter()
+        .filter(|doc| params.from.map(|from| doc.datetime.ge(&from)).unwrap_or(true))
+        .filter(|doc| params.to.map(|to| doc.datetime.le(&to)).unwrap_or(true))
+        .filter(|doc| {
+            matches!(
+                (&params.document_type, &doc.document_type),
+                (None, _) | (Some(DocumentTypeRequest::Account), DocumentType::Account(_)) | (Some(DocumentTypeRequest::Transaction), DocumentType::Trx(_))
+            )
+        })
         .cloned()
         .rev()
         .map(|doc| DocumentResponse {
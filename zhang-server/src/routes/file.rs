@@ -5,7 +5,7 @@ use tokio::sync::RwLock;
 use zhang_core::ledger::Ledger;
 
 use crate::request::FileUpdateRequest;
-use crate::response::{FileDetailResponse, ResponseWrapper};
+use crate::response::{FileDetailResponse, FileTreeNodeResponse, ResponseWrapper};
 use crate::{ApiResult, ReloadSender};
 
 pub async fn get_files(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResult<Vec<Option<String>>> {
@@ -21,6 +21,21 @@ pub async fn get_files(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResult<Vec<Opti
     ResponseWrapper::json(ret)
 }
 
+pub async fn get_file_tree(ledger: State<Arc<RwLock<Ledger>>>) -> ApiResult<Vec<FileTreeNodeResponse>> {
+    let ledger = ledger.read().await;
+    let entry_path = &ledger.entry.0;
+
+    let mut nodes = vec![];
+    for path in &ledger.visited_files {
+        if let Ok(striped_path) = path.strip_prefix(entry_path) {
+            let components = striped_path.iter().map(|it| it.to_string_lossy().to_string()).collect::<Vec<_>>();
+            FileTreeNodeResponse::insert(&mut nodes, "", &components);
+        }
+    }
+    FileTreeNodeResponse::sort(&mut nodes);
+    ResponseWrapper::json(nodes)
+}
+
 pub async fn get_file_content(ledger: State<Arc<RwLock<Ledger>>>, path: axum::extract::Path<(String,)>) -> ApiResult<FileDetailResponse> {
     let encoded_file_path = path.0 .0;
     let filename = String::from_utf8(base64::decode(encoded_file_path).unwrap()).unwrap();
@@ -42,7 +57,7 @@ pub async fn update_file_content(
 
     // todo(refact) check if the syntax valid
     // if parse_zhang(&payload.content, None).is_ok() {
-    ledger.data_source.async_save(&ledger, filename, payload.content.as_bytes()).await?;
+    ledger.async_save(filename, payload.content.as_bytes()).await?;
     reload_sender.reload();
     ResponseWrapper::<()>::created()
 }
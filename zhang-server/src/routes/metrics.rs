@@ -0,0 +1,107 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::{AppendHeaders, IntoResponse};
+use bigdecimal::Zero;
+use chrono::Utc;
+use itertools::Itertools;
+use tokio::sync::RwLock;
+use zhang_ast::amount::Amount;
+use zhang_ast::{Account, AccountType};
+use zhang_core::ledger::Ledger;
+use zhang_core::utils::calculable::Calculable;
+
+use crate::ServerResult;
+
+/// escape the label value in prometheus text format
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// export the balances of accounts, the net worth and the error count in prometheus text format
+pub async fn get_metrics(ledger: State<Arc<RwLock<Ledger>>>) -> ServerResult<impl IntoResponse> {
+    let ledger = ledger.read().await;
+    let timezone = &ledger.options.timezone;
+    let mut operations = ledger.operations();
+
+    let mut content = String::new();
+    let mut net_worth_amounts = vec![];
+
+    writeln!(content, "# HELP zhang_account_balance The latest balance of account in each commodity.").unwrap();
+    writeln!(content, "# TYPE zhang_account_balance gauge").unwrap();
+    let balances = operations
+        .all_account_balances()?
+        .into_iter()
+        .sorted_by(|a, b| a.account.cmp(&b.account).then(a.balance_commodity.cmp(&b.balance_commodity)));
+    for balance in balances {
+        writeln!(
+            content,
+            "zhang_account_balance{{account=\"{}\",currency=\"{}\"}} {}",
+            escape_label_value(&balance.account),
+            escape_label_value(&balance.balance_commodity),
+            balance.balance_number
+        )
+        .unwrap();
+        let account = Account::from_str(&balance.account)?;
+        if account.account_type == AccountType::Assets || account.account_type == AccountType::Liabilities {
+            net_worth_amounts.push(Amount::new(balance.balance_number, balance.balance_commodity));
+        }
+    }
+
+    let now = Utc::now().with_timezone(timezone);
+    let net_worth = net_worth_amounts.calculate(now, &mut operations)?;
+    writeln!(
+        content,
+        "# HELP zhang_net_worth The net worth of assets and liabilities in operating currency, commodities without price are excluded."
+    )
+    .unwrap();
+    writeln!(content, "# TYPE zhang_net_worth gauge").unwrap();
+    writeln!(
+        content,
+        "zhang_net_worth{{currency=\"{}\"}} {}",
+        escape_label_value(&net_worth.calculated.currency),
+        net_worth.calculated.number
+    )
+    .unwrap();
+
+    writeln!(
+        content,
+        "# HELP zhang_net_worth_by_commodity The net worth of assets and liabilities in each commodity before conversion."
+    )
+    .unwrap();
+    writeln!(content, "# TYPE zhang_net_worth_by_commodity gauge").unwrap();
+    let mut unconverted_commodities = 0;
+    for (commodity, number) in net_worth.detail.iter().sorted_by(|a, b| a.0.cmp(b.0)) {
+        writeln!(
+            content,
+            "zhang_net_worth_by_commodity{{currency=\"{}\"}} {}",
+            escape_label_value(commodity),
+            number
+        )
+        .unwrap();
+        if commodity.ne(&net_worth.calculated.currency)
+            && !number.is_zero()
+            && operations.get_price(now.naive_local(), commodity, &net_worth.calculated.currency)?.is_none()
+        {
+            unconverted_commodities += 1;
+        }
+    }
+
+    writeln!(
+        content,
+        "# HELP zhang_net_worth_unconverted_commodities The number of commodities excluded from net worth since no price is found."
+    )
+    .unwrap();
+    writeln!(content, "# TYPE zhang_net_worth_unconverted_commodities gauge").unwrap();
+    writeln!(content, "zhang_net_worth_unconverted_commodities {}", unconverted_commodities).unwrap();
+
+    writeln!(content, "# HELP zhang_errors The number of errors in ledger.").unwrap();
+    writeln!(content, "# TYPE zhang_errors gauge").unwrap();
+    writeln!(content, "zhang_errors {}", operations.errors()?.len()).unwrap();
+
+    let headers = AppendHeaders([(header::CONTENT_TYPE, "text/plain; version=0.0.4")]);
+    Ok((headers, content))
+}
@@ -4,6 +4,7 @@ pub mod commodity;
 pub mod common;
 pub mod document;
 pub mod file;
+pub mod metrics;
 pub mod statistics;
 pub mod transaction;
 
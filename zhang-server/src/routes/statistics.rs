@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -8,18 +8,26 @@ use itertools::Itertools;
 use tokio::sync::RwLock;
 use zhang_ast::amount::Amount;
 use zhang_ast::{Account, AccountType, Flag};
+use zhang_core::domains::schemas::AverageMonthlySpendDomain;
 use zhang_core::ledger::Ledger;
 use zhang_core::utils::calculable::Calculable;
 use zhang_core::utils::date_range::NaiveDateRange;
 
-use crate::request::{StatisticGraphRequest, StatisticRequest};
-use crate::response::{ReportRankItemResponse, ResponseWrapper, StatisticGraphResponse, StatisticRankResponse, StatisticSummaryResponse};
+use crate::request::{AverageMonthlySpendRequest, FinancialStatementRequest, MetaSumRequest, StatisticGraphRequest, StatisticRequest};
+use crate::response::{
+    FinancialStatementsResponse, MetaSumResponse, ReportRankItemResponse, ResponseWrapper, StatisticGraphResponse, StatisticRankResponse,
+    StatisticSummaryResponse,
+};
 use crate::ApiResult;
 
 pub async fn get_statistic_summary(ledger: State<Arc<RwLock<Ledger>>>, params: Query<StatisticRequest>) -> ApiResult<StatisticSummaryResponse> {
     let ledger = ledger.read().await;
     let timezone = &ledger.options.timezone;
     let mut operations = ledger.operations();
+    let transfers = match params.exclude_transfers {
+        Some(true) => operations.transfer_transaction_ids()?,
+        _ => HashSet::new(),
+    };
 
     let accounts = operations.all_accounts()?;
     // balance
@@ -58,6 +66,7 @@ pub async fn get_statistic_summary(ledger: State<Arc<RwLock<Ledger>>>, params: Q
         .filter(|posting| posting.trx_datetime.ge(&params.from))
         .filter(|posting| posting.trx_datetime.le(&params.to))
         .filter(|posting| posting.account.account_type == AccountType::Income)
+        .filter(|posting| !transfers.contains(&posting.trx_id))
         .map(|posting| posting.inferred_amount.clone())
         .collect_vec();
 
@@ -70,6 +79,7 @@ pub async fn get_statistic_summary(ledger: State<Arc<RwLock<Ledger>>>, params: Q
         .filter(|posting| posting.trx_datetime.ge(&params.from))
         .filter(|posting| posting.trx_datetime.le(&params.to))
         .filter(|posting| posting.account.account_type == AccountType::Expenses)
+        .filter(|posting| !transfers.contains(&posting.trx_id))
         .map(|posting| posting.inferred_amount.clone())
         .collect_vec();
     let expense = expense_amounts.calculate(params.to.with_timezone(timezone), &mut operations)?;
@@ -81,6 +91,7 @@ pub async fn get_statistic_summary(ledger: State<Arc<RwLock<Ledger>>>, params: Q
         .filter(|trx| trx.flag != Flag::BalanceCheck || trx.flag != Flag::BalancePad)
         .filter(|trx| trx.datetime.ge(&params.from))
         .filter(|trx| trx.datetime.le(&params.to))
+        .filter(|trx| !transfers.contains(&trx.id))
         .count();
 
     ResponseWrapper::json(StatisticSummaryResponse {
@@ -119,10 +130,14 @@ pub async fn get_statistic_graph(ledger: State<Arc<RwLock<Ledger>>>, params: Que
         dated_balance.insert(date, balance);
     }
 
+    let transfers = match params.exclude_transfers {
+        Some(true) => operations.transfer_transaction_ids()?,
+        _ => HashSet::new(),
+    };
     let mut dated_change = HashMap::new();
     let postings = operations.dated_journals(params.from, params.to)?;
 
-    for posting in postings {
+    for posting in postings.into_iter().filter(|posting| !transfers.contains(&posting.trx_id)) {
         let date = posting.trx_datetime.naive_local().date();
         let account_type_store = dated_change.entry(date).or_insert_with(HashMap::new);
         let currency_store = account_type_store.entry(posting.account.account_type).or_insert_with(Vec::new);
@@ -156,7 +171,15 @@ pub async fn get_statistic_rank_detail_by_account_type(
     let timezone = &ledger.options.timezone;
     let mut operations = ledger.operations();
 
-    let income_transactions = operations.account_type_dated_journals(account_type, params.from, params.to)?;
+    let transfers: HashSet<String> = match params.exclude_transfers {
+        Some(true) => operations.transfer_transaction_ids()?.into_iter().map(|id| id.to_string()).collect(),
+        _ => HashSet::new(),
+    };
+    let income_transactions = operations
+        .account_type_dated_journals(account_type, params.from, params.to)?
+        .into_iter()
+        .filter(|journal| !transfers.contains(&journal.trx_id))
+        .collect_vec();
 
     let mut account_detail: HashMap<String, Vec<Amount>> = HashMap::new();
 
@@ -192,3 +215,37 @@ pub async fn get_statistic_rank_detail_by_account_type(
         top_transactions,
     })
 }
+
+pub async fn get_financial_statements(ledger: State<Arc<RwLock<Ledger>>>, params: Query<FinancialStatementRequest>) -> ApiResult<FinancialStatementsResponse> {
+    let ledger = ledger.read().await;
+    let timezone = &ledger.options.timezone;
+    let mut operations = ledger.operations();
+
+    let balance_sheet = operations.balance_sheet(params.as_of.with_timezone(timezone))?;
+    let income_statement = operations.income_statement(params.from.with_timezone(timezone), params.to.with_timezone(timezone))?;
+    ResponseWrapper::json(FinancialStatementsResponse {
+        balance_sheet,
+        income_statement,
+    })
+}
+
+pub async fn get_meta_sum(ledger: State<Arc<RwLock<Ledger>>>, params: Query<MetaSumRequest>) -> ApiResult<MetaSumResponse> {
+    let ledger = ledger.read().await;
+    let operations = ledger.operations();
+
+    let total = operations.sum_transaction_meta(&params.key, params.from, params.to)?;
+    ResponseWrapper::json(MetaSumResponse {
+        key: params.key.clone(),
+        total,
+    })
+}
+
+pub async fn get_average_monthly_spend(ledger: State<Arc<RwLock<Ledger>>>, params: Query<AverageMonthlySpendRequest>) -> ApiResult<AverageMonthlySpendDomain> {
+    let ledger = ledger.read().await;
+    let timezone = &ledger.options.timezone;
+    let mut operations = ledger.operations();
+
+    let as_of = params.as_of.unwrap_or_else(Utc::now).with_timezone(timezone);
+    let average_monthly_spend = operations.average_monthly_spend(&params.category, params.months, as_of)?;
+    ResponseWrapper::json(average_monthly_spend)
+}
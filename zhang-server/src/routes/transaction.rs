@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -14,11 +15,12 @@ use zhang_core::domains::schemas::MetaType;
 use zhang_core::ledger::Ledger;
 use zhang_core::store::TransactionDomain;
 use zhang_core::utils::string_::{escape_with_quote, StringExt};
+use zhang_core::ZhangError;
 
-use crate::request::{CreateTransactionRequest, JournalRequest};
+use crate::request::{AnnotateTransactionRequest, ConvertTransactionRequest, CreateTransactionRequest, JournalRequest};
 use crate::response::{
-    InfoForNewTransaction, JournalBalanceCheckItemResponse, JournalBalancePadItemResponse, JournalItemResponse, JournalTransactionItemResponse,
-    JournalTransactionPostingResponse, Pageable, ResponseWrapper,
+    AmountResponse, InfoForNewTransaction, JournalBalanceCheckItemResponse, JournalBalancePadItemResponse, JournalItemResponse, JournalTransactionItemResponse,
+    JournalTransactionPostingResponse, Pageable, ResponseWrapper, TransactionPostingResponse, TransactionResponse,
 };
 use crate::{ApiResult, ReloadSender};
 
@@ -41,14 +43,12 @@ pub async fn get_journals(ledger: State<Arc<RwLock<Ledger>>>, params: Query<Jour
     let mut operations = ledger.operations();
     let params = params.0;
 
-    let total_count = operations.transaction_counts()?;
-
     let store = operations.read();
 
-    let journals: Vec<TransactionDomain> = store
-        .transactions
-        .values()
-        .filter(|it| params.keyword.as_ref().map(|keyword| it.contains_keyword(keyword)).unwrap_or(true))
+    let matched_journals = store.transactions.values().filter(|it| params.is_matched(it)).collect_vec();
+    let total_count = matched_journals.len();
+    let journals: Vec<TransactionDomain> = matched_journals
+        .into_iter()
         .sorted_by_key(|it| -it.sequence)
         .skip(params.offset() as usize)
         .take(params.limit() as usize)
@@ -116,6 +116,11 @@ pub async fn get_journals(ledger: State<Arc<RwLock<Ledger>>>, params: Query<Jour
                 })
             }
             _ => {
+                let is_transfer = journal_item.is_transfer();
+                let rounding_residual = journal_item
+                    .rounding_residual
+                    .clone()
+                    .map(|residual| residual.into_iter().map(AmountResponse::from).collect_vec());
                 let postings = journal_item
                     .postings
                     .into_iter()
@@ -151,6 +156,8 @@ pub async fn get_journals(ledger: State<Arc<RwLock<Ledger>>>, params: Query<Jour
                     links,
                     flag: journal_item.flag.to_string(),
                     is_balanced: true,
+                    is_transfer,
+                    rounding_residual,
                     postings,
                     metas,
                 })
@@ -192,13 +199,167 @@ pub async fn create_new_transaction(
         flag: Some(Flag::Okay),
         payee: Some(payload.payee.to_quote()),
         narration: payload.narration.map(|it| it.to_quote()),
-        tags: IndexSet::from_iter(payload.tags.into_iter()),
-        links: IndexSet::from_iter(payload.links.into_iter()),
+        tags: IndexSet::from_iter(payload.tags),
+        links: IndexSet::from_iter(payload.links),
         postings,
         meta: metas,
     });
 
-    ledger.data_source.async_append(&ledger, vec![trx]).await?;
+    ledger.async_append(vec![trx]).await?;
+    reload_sender.reload();
+    ResponseWrapper::json("Ok".to_string())
+}
+
+/// clone the transaction located at the given span into a new transaction,
+/// in which every posting amount not in target commodity is converted with the given rate.
+pub async fn convert_transaction(
+    ledger: State<Arc<RwLock<Ledger>>>, reload_sender: State<Arc<ReloadSender>>, Json(payload): Json<ConvertTransactionRequest>,
+) -> ApiResult<TransactionResponse> {
+    let ledger = ledger.read().await;
+
+    let span = payload.span;
+    let origin = ledger
+        .transaction_by_span(&PathBuf::from(&span.filename), span.start)?
+        .ok_or_else(|| ZhangError::TransactionNotFound(format!("{}:{}", span.filename, span.start)))?;
+
+    // date and meta are taken from the origin directive, since the store only keeps the datetime of transaction
+    let origin_directive = ledger
+        .directives
+        .iter()
+        .find(|it| it.span.start == origin.span.start && it.span.filename == origin.span.filename)
+        .map(|it| it.data.clone());
+    let Some(Directive::Transaction(origin_trx)) = origin_directive else {
+        return Err(ZhangError::TransactionNotFound(format!("{}:{}", span.filename, span.start)).into());
+    };
+
+    // postings carrying cost or price hold commodities which cannot be converted by a currency rate
+    if origin.postings.iter().any(|posting| {
+        posting.cost.is_some()
+            || posting
+                .unit
+                .as_ref()
+                .map(|unit| unit.currency.ne(&posting.inferred_amount.currency))
+                .unwrap_or(false)
+    }) {
+        return Err(ZhangError::InconvertibleTransaction("posting with cost or price cannot be converted".to_owned()).into());
+    }
+    let source_currency = match payload.from {
+        Some(from) => from,
+        None => {
+            let currencies = origin
+                .postings
+                .iter()
+                .map(|posting| &posting.inferred_amount.currency)
+                .filter(|currency| currency.ne(&&payload.commodity))
+                .unique()
+                .collect_vec();
+            match currencies.as_slice() {
+                [currency] => (*currency).clone(),
+                _ => return Err(ZhangError::InconvertibleTransaction("source currency must be specified".to_owned()).into()),
+            }
+        }
+    };
+
+    let mut postings = vec![];
+    for posting in origin.postings.into_iter() {
+        let amount = posting.inferred_amount;
+        let units = if amount.currency.eq(&source_currency) {
+            Amount::new(amount.number * &payload.rate, payload.commodity.clone())
+        } else {
+            amount
+        };
+        postings.push(Posting {
+            flag: None,
+            account: posting.account,
+            units: Some(units),
+            cost: None,
+            cost_date: None,
+            price: None,
+            comment: None,
+            meta: Default::default(),
+        });
+    }
+    let trx = Transaction {
+        date: origin_trx.date,
+        flag: Some(origin.flag.clone()),
+        payee: origin.payee.map(|it| it.to_quote()),
+        narration: origin.narration.map(|it| it.to_quote()),
+        tags: IndexSet::from_iter(origin.tags),
+        links: IndexSet::from_iter(origin.links),
+        postings,
+        meta: origin_trx.meta,
+    };
+    if !ledger.is_transaction_balanced(&trx)? {
+        return Err(ZhangError::UnbalancedTransaction.into());
+    }
+
+    let response = TransactionResponse {
+        datetime: origin.datetime.naive_local(),
+        payee: trx.payee.as_ref().map(|it| it.as_str().to_owned()).unwrap_or_default(),
+        narration: trx.narration.as_ref().map(|it| it.as_str().to_owned()),
+        tags: trx.tags.iter().cloned().collect_vec(),
+        links: trx.links.iter().cloned().collect_vec(),
+        flag: origin.flag.to_string(),
+        postings: trx
+            .postings
+            .iter()
+            .map(|posting| TransactionPostingResponse {
+                account: posting.account.name().to_owned(),
+                unit: posting.units.clone().map(AmountResponse::from),
+            })
+            .collect_vec(),
+    };
+
+    ledger.async_append(vec![Directive::Transaction(trx)]).await?;
+    reload_sender.reload();
+    ResponseWrapper::json(response)
+}
+
+/// insert a meta line right under the header of transaction, postings and other lines of transaction are kept untouched
+pub async fn annotate_transaction(
+    ledger: State<Arc<RwLock<Ledger>>>, reload_sender: State<Arc<ReloadSender>>, Json(payload): Json<AnnotateTransactionRequest>,
+) -> ApiResult<String> {
+    let ledger = ledger.read().await;
+
+    let span = payload.span;
+    let origin = ledger
+        .transaction_by_span(&PathBuf::from(&span.filename), span.start)?
+        .ok_or_else(|| ZhangError::TransactionNotFound(format!("{}:{}", span.filename, span.start)))?;
+
+    let location = format!("{}:{}", span.filename, span.start);
+    // read and write the file the transaction is loaded from, whose path is in the form of the data source
+    let filename = origin
+        .span
+        .filename
+        .as_ref()
+        .map(|it| it.to_string_lossy().to_string())
+        .unwrap_or_else(|| span.filename.clone());
+    let mut content = String::from_utf8(ledger.data_source.async_get(filename.clone()).await?).map_err(ZhangError::from)?;
+    // the span comes from the last loading, the file may be modified since then
+    let Some(transaction_content) = content.get(origin.span.start..origin.span.end) else {
+        return Err(ZhangError::StaleTransactionSpan(location).into());
+    };
+    let header = transaction_content.lines().next().unwrap_or_default();
+    let is_header_matched = header.starts_with(&origin.datetime.format("%Y-%m-%d").to_string())
+        && origin.payee.iter().chain(origin.narration.iter()).all(|it| header.contains(it.as_str()));
+    if !is_header_matched {
+        return Err(ZhangError::StaleTransactionSpan(location).into());
+    }
+    let Some(header_end) = transaction_content.find('\n') else {
+        return Err(ZhangError::TransactionWithoutPosting(location).into());
+    };
+    // follow the indentation of the first line under the header
+    let indentation: String = transaction_content[header_end + 1..].chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+    let indentation = if indentation.is_empty() { "  ".to_owned() } else { indentation };
+    let key = if !payload.key.is_empty() && payload.key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+        payload.key.clone()
+    } else {
+        escape_with_quote(&payload.key).to_string()
+    };
+    let meta_line = format!("\n{}{}: {}", indentation, key, escape_with_quote(&payload.value));
+    content.insert_str(origin.span.start + header_end, &meta_line);
+
+    ledger.async_save(filename, content.as_bytes()).await?;
     reload_sender.reload();
     ResponseWrapper::json("Ok".to_string())
 }
@@ -210,7 +371,6 @@ pub async fn upload_transaction_document(
     let transaction_id = path.0 .0;
     let ledger = ledger.read().await;
     let mut operations = ledger.operations();
-    let entry = &ledger.entry.0;
     let mut documents = vec![];
 
     while let Some(field) = multipart.next_field().await.unwrap() {
@@ -219,20 +379,15 @@ pub async fn upload_transaction_document(
         let _content_type = field.content_type().unwrap().to_string();
 
         let v4 = Uuid::new_v4();
-        let buf = entry.join("attachments").join(v4.to_string()).join(&file_name);
-        let striped_buf = buf.strip_prefix(entry).unwrap();
-        let striped_path_string = striped_buf.to_string_lossy().to_string();
+        // the document filename is relative to documents directory, while the saving path is relative to entry directory
+        let document_path = PathBuf::from("attachments").join(v4.to_string()).join(&file_name).to_string_lossy().to_string();
+        let striped_path_string = ledger.resolve_document_path(&document_path)?.to_string_lossy().to_string();
         info!("uploading document `{}`(id={}) to transaction {}", file_name, &v4.to_string(), &transaction_id);
         let content_buf = field.bytes().await.unwrap();
 
         ledger.data_source.async_save(&ledger, striped_path_string, &content_buf).await?;
 
-        let path = match buf.strip_prefix(entry) {
-            Ok(relative_path) => relative_path.to_str().unwrap(),
-            Err(_) => buf.to_str().unwrap(),
-        };
-
-        documents.push(ZhangString::QuoteString(path.to_string()));
+        documents.push(ZhangString::QuoteString(document_path));
     }
     let span_info = operations.transaction_span(&transaction_id)?;
     let metas_content = documents
@@ -244,7 +399,7 @@ pub async fn upload_transaction_document(
     let mut content = String::from_utf8(ledger.data_source.async_get(source_file_path.clone()).await?).unwrap();
     content.insert(span_info.span_end, '\n');
     content.insert_str(span_info.span_end + 1, &metas_content);
-    ledger.data_source.async_save(&ledger, source_file_path, content.as_bytes()).await?;
+    ledger.async_save(source_file_path, content.as_bytes()).await?;
     reload_sender.reload();
     ResponseWrapper::json("Ok".to_string())
 }